@@ -0,0 +1,455 @@
+//! Opt-in HTTP API on localhost for driving a running timer, e.g. from editor
+//! plugins and browser extensions.
+//!
+//! ```toml
+//! [api]
+//! port = 7070
+//! token = "a long random string"
+//! ```
+//!
+//! Every request needs an `Authorization: Bearer <token>` header.
+//!
+//! - `GET /status`: the current phase as JSON
+//! - `POST /start`: starts a set while `pompom daemon` waits for its schedule
+//! - `POST /pause` and `POST /resume`: pause and resume the current phase
+//! - `POST /skip`: ends the current phase now
+//! - `GET /history?from=2026-10-01&to=2026-10-19`: logged sessions, both
+//!   dates optional
+//! - `GET /events`: a Server-Sent Events stream of transitions
+//! - `GET /timers`: the [named timers](crate::timers) of the daemon, and
+//!   `GET /timers/<name>` one of them
+//! - `POST /timers/<name>`: starts a timer from a JSON
+//!   [`NewTimer`](crate::timers::NewTimer)
+//! - `POST /timers/<name>/pause`, `/resume`, `/skip` and `/stop`: drive one
+//!   timer
+
+use std::{
+    convert::Infallible,
+    fmt,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use chrono::{NaiveDate, Utc};
+use hyper::{
+    body::Bytes,
+    header::{self, HeaderValue},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use miette::{IntoDiagnostic, WrapErr};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{
+    db::Database,
+    error::PomodoroError,
+    events::Event,
+    export::DateRange,
+    hooks::{HookContext, HookEvent},
+    schedule,
+    timers::{self, NewTimer, Timers},
+};
+
+/// The `[api]` table of the config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiConfig {
+    /// Port on `127.0.0.1`, `0` picks a free one.
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Bearer token every request must carry.
+    pub token: String,
+}
+
+fn default_port() -> u16 {
+    7070
+}
+
+/// What a client asks the timer to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Start,
+    Pause,
+    Resume,
+    Skip,
+    /// Ends the set.
+    Stop,
+    /// Ends the set like `Ctrl+c`, which stops `pompom daemon` too.
+    Cancel,
+}
+
+/// Why a command wasn't passed on to the timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refusal {
+    /// The command doesn't fit the timer's state, e.g. resuming while running.
+    Conflict(&'static str),
+    /// The timer is gone.
+    Stopped,
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::Conflict(reason) => f.write_str(reason),
+            Refusal::Stopped => f.write_str("The timer has stopped"),
+        }
+    }
+}
+
+/// A state transition, as broadcast to `GET /events` and D-Bus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    /// A hook event such as `work_start`, or `resumed`.
+    pub event: String,
+    /// The transition as a JSON object.
+    pub data: String,
+}
+
+/// The timer as reported by `GET /status`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Status {
+    /// `idle`, `work`, `short_break` or `long_break`.
+    pub state: String,
+    pub paused: bool,
+    pub task: Option<String>,
+    pub cycle: u16,
+    pub cycles: u16,
+    /// Seconds left in the phase.
+    pub remaining: u64,
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        Self {
+            state: "idle".to_string(),
+            paused: false,
+            task: None,
+            cycle: 0,
+            cycles: 0,
+            remaining: 0,
+        }
+    }
+}
+
+/// The timer's end of the API: the status it publishes, the commands it
+/// receives and the transitions it broadcasts.
+#[derive(Debug)]
+pub struct Remote {
+    status: Mutex<Status>,
+    /// A `Start` was accepted, and its set hasn't published a phase yet.
+    starting: AtomicBool,
+    commands: mpsc::UnboundedSender<Command>,
+    events: broadcast::Sender<Transition>,
+}
+
+impl Remote {
+    /// A remote, and the receiving end of the commands sent through it.
+    pub fn new() -> (Arc<Self>, mpsc::UnboundedReceiver<Command>) {
+        let (commands, receiver) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(64);
+        let remote = Self {
+            status: Mutex::new(Status::default()),
+            starting: AtomicBool::new(false),
+            commands,
+            events,
+        };
+        (Arc::new(remote), receiver)
+    }
+
+    pub fn status(&self) -> Status {
+        self.status.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    pub fn update(&self, f: impl FnOnce(&mut Status)) {
+        f(&mut self.status.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+
+    /// Passes `data` on to everyone subscribed to transitions.
+    pub fn broadcast(&self, event: &str, data: &str) {
+        // No one listening isn't an error.
+        let _ = self.events.send(Transition { event: event.to_string(), data: data.to_string() });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Transition> {
+        self.events.subscribe()
+    }
+
+    /// Updates the status to after `event`, and broadcasts it if it's a
+    /// transition.
+    pub fn apply(&self, event: &Event) {
+        match event {
+            Event::Tick { remaining, .. } => {
+                self.update(|status| status.remaining = remaining.as_secs());
+            }
+            Event::Resumed { phase, remaining } => {
+                self.update(|status| status.paused = false);
+                let context = HookContext::new(phase, *remaining);
+                self.broadcast("resumed", &context.to_json("resumed", Utc::now()));
+            }
+            _ => {
+                let Some((hook, context)) = HookEvent::of(event) else {
+                    return;
+                };
+                self.starting.store(false, Ordering::SeqCst);
+                self.update(|status| {
+                    *status = match hook {
+                        HookEvent::SetEnd | HookEvent::Interrupted => Status::default(),
+                        _ => Status {
+                            state: context.state.to_string(),
+                            paused: hook == HookEvent::Paused,
+                            task: context.task.map(str::to_string),
+                            cycle: context.cycle,
+                            cycles: context.cycles,
+                            remaining: context.remaining.as_secs(),
+                        },
+                    };
+                });
+                self.broadcast(hook.as_str(), &context.to_json(hook.as_str(), Utc::now()));
+            }
+        }
+    }
+
+    /// Passes `command` on to the timer if it fits its state, returning the
+    /// status it was sent in.
+    pub fn send(&self, command: Command) -> Result<Status, Refusal> {
+        let status = self.status();
+        let idle = status.state == "idle";
+        let conflict = match command {
+            Command::Start if !idle => Some("A set is already running"),
+            // Still idle until the set publishes its first phase, when a
+            // second `Start` would queue another set.
+            Command::Start if self.starting.swap(true, Ordering::SeqCst) => {
+                Some("A set is already starting")
+            }
+            Command::Start => None,
+            _ if idle => Some("No set is running"),
+            Command::Pause if status.paused => Some("Already paused"),
+            Command::Resume if !status.paused => Some("Not paused"),
+            Command::Pause | Command::Resume | Command::Skip | Command::Stop | Command::Cancel => {
+                None
+            }
+        };
+        if let Some(conflict) = conflict {
+            return Err(Refusal::Conflict(conflict));
+        }
+        if self.commands.send(command).is_err() {
+            self.starting.store(false, Ordering::SeqCst);
+            return Err(Refusal::Stopped);
+        }
+        Ok(status)
+    }
+}
+
+/// Serves the API on `127.0.0.1` in the background, returning the address
+/// it listens on. The endpoints outside `/timers` drive [`timers::MAIN`].
+pub fn serve(config: &ApiConfig, timers: Arc<Timers>) -> miette::Result<SocketAddr> {
+    if config.token.is_empty() {
+        return Err(PomodoroError::NoApiToken.into());
+    }
+    let token: Arc<str> = Arc::from(config.token.as_str());
+    let make_service = make_service_fn(move |_conn| {
+        let timers = Arc::clone(&timers);
+        let token = Arc::clone(&token);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let timers = Arc::clone(&timers);
+                let token = Arc::clone(&token);
+                async move { Ok::<_, Infallible>(handle(&timers, &token, req).await) }
+            }))
+        }
+    });
+    let addr = SocketAddr::from(([127, 0, 0, 1], config.port));
+    let server = Server::try_bind(&addr)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to start the HTTP API on {addr}"))?
+        .serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            log::warn!("HTTP API stopped: {e}");
+        }
+    });
+    Ok(addr)
+}
+
+async fn handle(timers: &Arc<Timers>, token: &str, req: Request<Body>) -> Response<Body> {
+    if !authorized(&req, token) {
+        let mut response = error(StatusCode::UNAUTHORIZED, "Missing or wrong bearer token");
+        response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        return response;
+    }
+    let path = req.uri().path().to_string();
+    if path == "/timers" || path.starts_with("/timers/") {
+        return named(timers, req, &path).await;
+    }
+    let remote = &timers.main();
+    match (req.method(), path.as_str()) {
+        (&Method::GET, "/status") => json(StatusCode::OK, &remote.status()),
+        (&Method::POST, "/start") => command(remote, Command::Start),
+        (&Method::POST, "/pause") => command(remote, Command::Pause),
+        (&Method::POST, "/resume") => command(remote, Command::Resume),
+        (&Method::POST, "/skip") => command(remote, Command::Skip),
+        (&Method::GET, "/history") => history(req.uri().query()),
+        (&Method::GET, "/events") => events(remote),
+        (_, "/status" | "/start" | "/pause" | "/resume" | "/skip" | "/history" | "/events") => {
+            error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed")
+        }
+        _ => error(StatusCode::NOT_FOUND, "No such endpoint"),
+    }
+}
+
+fn authorized(req: &Request<Body>, token: &str) -> bool {
+    let Some(given) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    // Compare every byte so the time taken doesn't give the token away.
+    given.len() == token.len()
+        && given.bytes().zip(token.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// `/timers` and the paths under it.
+async fn named(timers: &Arc<Timers>, req: Request<Body>, path: &str) -> Response<Body> {
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let no_timer = |name: &str| error(StatusCode::NOT_FOUND, &format!("No timer named `{name}`"));
+    match (req.method(), &segments[1..]) {
+        (&Method::GET, []) => json(StatusCode::OK, &timers.list()),
+        (&Method::GET, [name]) => match timers.list().into_iter().find(|t| t.name == *name) {
+            Some(timer) => json(StatusCode::OK, &timer),
+            None => no_timer(name),
+        },
+        (&Method::POST, [name]) => start(timers, name, req).await,
+        (&Method::POST, [name, action @ ("pause" | "resume" | "skip" | "stop")]) => {
+            let Some(remote) = timers.remote(name) else {
+                return no_timer(name);
+            };
+            let action = match *action {
+                "pause" => Command::Pause,
+                "resume" => Command::Resume,
+                "skip" => Command::Skip,
+                _ => Command::Stop,
+            };
+            command(&remote, action)
+        }
+        (_, [] | [_] | [_, "pause" | "resume" | "skip" | "stop"]) => {
+            error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed")
+        }
+        _ => error(StatusCode::NOT_FOUND, "No such endpoint"),
+    }
+}
+
+/// Starts the timer `name` from the [`NewTimer`] in the body of `req`.
+async fn start(timers: &Arc<Timers>, name: &str, req: Request<Body>) -> Response<Body> {
+    if !timers::valid_name(name) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Timer names are letters, digits, `-` and `_`, e.g. `standup`",
+        );
+    }
+    let new = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => serde_json::from_slice::<NewTimer>(&body),
+        Err(e) => return error(StatusCode::BAD_REQUEST, &format!("Failed to read the body: {e}")),
+    };
+    let new = match new {
+        Ok(new) => new,
+        Err(e) => return error(StatusCode::BAD_REQUEST, &format!("Invalid timer: {e}")),
+    };
+    match timers.start(name, new).await {
+        Ok(timer) => json(StatusCode::CREATED, &timer),
+        Err(refusal) => refused(refusal),
+    }
+}
+
+fn command(remote: &Remote, command: Command) -> Response<Body> {
+    match remote.send(command) {
+        Ok(status) => json(StatusCode::ACCEPTED, &status),
+        Err(refusal) => refused(refusal),
+    }
+}
+
+fn refused(refusal: Refusal) -> Response<Body> {
+    match refusal {
+        Refusal::Conflict(_) => error(StatusCode::CONFLICT, &refusal.to_string()),
+        Refusal::Stopped => error(StatusCode::SERVICE_UNAVAILABLE, &refusal.to_string()),
+    }
+}
+
+fn history(query: Option<&str>) -> Response<Body> {
+    let mut range = DateRange { from: None, to: None };
+    for pair in query.unwrap_or_default().split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let Ok(date) = value.parse::<NaiveDate>() else {
+            return error(
+                StatusCode::BAD_REQUEST,
+                &format!("`{value}` is not a date like 2026-10-19"),
+            );
+        };
+        match key {
+            "from" => range.from = Some(date),
+            "to" => range.to = Some(date),
+            _ => return error(StatusCode::BAD_REQUEST, &format!("Unknown parameter `{key}`")),
+        }
+    }
+    let tz = schedule::local_timezone();
+    match Database::open_default().and_then(|db| db.sessions()) {
+        Ok(sessions) => {
+            let sessions: Vec<_> = sessions
+                .into_iter()
+                .filter(|s| range.contains(s.started_at.with_timezone(&tz).date_naive()))
+                .collect();
+            json(StatusCode::OK, &sessions)
+        }
+        Err(e) => error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to read session history: {e}"),
+        ),
+    }
+}
+
+/// Streams transitions, starting with the current status.
+fn events(remote: &Remote) -> Response<Body> {
+    let mut receiver = remote.subscribe();
+    let status = serde_json::to_string(&remote.status()).unwrap_or_default();
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let status = format!("event: status\ndata: {status}\n\n");
+        if sender.send_data(Bytes::from(status)).await.is_err() {
+            return;
+        }
+        loop {
+            let event = match receiver.recv().await {
+                Ok(Transition { event, data }) => format!("event: {event}\ndata: {data}\n\n"),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if sender.send_data(Bytes::from(event)).await.is_err() {
+                break;
+            }
+        }
+    });
+    let mut response = Response::new(body);
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+fn json<T: Serialize>(status: StatusCode, value: &T) -> Response<Body> {
+    let mut response = Response::new(Body::from(serde_json::to_string(value).unwrap_or_default()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    json(status, &serde_json::json!({ "error": message }))
+}
@@ -1,8 +1,3 @@
-use std::{
-    env,
-    ffi::OsString,
-};
-
 use clap::Parser;
 use miette::{
     Context,
@@ -12,13 +7,13 @@ use pompom::*;
 
 #[tokio::main]
 async fn main() -> miette::Result<()> {
-    App::new(
-        PomoFocusCli::try_parse()
-            .into_diagnostic()
-            .wrap_err("Failed to parse command line arguments")?,
-    )
-    .run()
-    .await?;
+    let cli = PomoFocusCli::try_parse()
+        .into_diagnostic()
+        .wrap_err("Failed to parse command line arguments")?;
+    pretty_env_logger::formatted_builder()
+        .filter_level(cli.log_level_filter())
+        .init();
+    App::new(cli).run().await?;
 
     Ok(())
 }
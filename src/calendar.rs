@@ -0,0 +1,532 @@
+//! Busy time read from local iCalendar (`.ics`) files.
+//!
+//! Only what's needed to plan around meetings is understood: `VEVENT`s with
+//! `DTSTART`, `DTEND` or `DURATION`, `SUMMARY`, `TRANSP`, `STATUS`, simple
+//! daily or weekly `RRULE`s, `EXDATE`s and instances moved or cancelled with
+//! `RECURRENCE-ID`. All-day events, transparent ("free") events and cancelled
+//! events don't block time. An event that can't be read, e.g. for a `TZID`
+//! that isn't an IANA name, is skipped with a warning.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use miette::{IntoDiagnostic, WrapErr};
+
+use crate::{config::Config, plan::Busy};
+
+/// Stop expanding a recurring event after this many occurrences.
+const MAX_OCCURRENCES: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub summary: Option<String>,
+    /// Wall-clock start in `tz`.
+    pub start: NaiveDateTime,
+    pub tz: Tz,
+    pub duration: Duration,
+    pub rrule: Option<RRule>,
+    /// Starts of occurrences that don't happen, from `EXDATE` or instances
+    /// moved elsewhere.
+    pub exdates: Vec<DateTime<Utc>>,
+}
+
+/// The events of an iCalendar document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Calendar {
+    pub events: Vec<Event>,
+    /// Why each event that couldn't be read was skipped.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freq {
+    Daily,
+    Weekly,
+}
+
+/// The subset of RFC 5545 recurrence rules used by most meeting invites.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    pub freq: Freq,
+    pub interval: u32,
+    pub count: Option<usize>,
+    pub until: Option<DateTime<Utc>>,
+    pub by_day: Vec<Weekday>,
+}
+
+impl Event {
+    /// Busy periods of this event overlapping `from..to`.
+    pub fn busy_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Busy> {
+        let to_busy = |start: NaiveDateTime| {
+            let start = self.tz.from_local_datetime(&start).earliest()?.with_timezone(&Utc);
+            Some(Busy { start, end: start + self.duration, label: self.summary.clone() })
+        };
+        let Some(rrule) = &self.rrule else {
+            return to_busy(self.start)
+                .into_iter()
+                .filter(|b| b.end > from && b.start < to)
+                .collect();
+        };
+
+        let interval = i64::from(rrule.interval.max(1));
+        let mut busy = Vec::new();
+        let mut seen = 0;
+        let mut period = 0;
+        'periods: loop {
+            let starts: Vec<NaiveDateTime> = match rrule.freq {
+                Freq::Daily => vec![self.start + Duration::days(period * interval)],
+                Freq::Weekly => {
+                    let week = self.start + Duration::weeks(period * interval);
+                    let monday = week.date()
+                        - Duration::days(i64::from(week.weekday().num_days_from_monday()));
+                    let mut days = if rrule.by_day.is_empty() {
+                        vec![self.start.weekday()]
+                    } else {
+                        rrule.by_day.clone()
+                    };
+                    days.sort_by_key(Weekday::num_days_from_monday);
+                    days.iter()
+                        .map(|d| {
+                            (monday + Duration::days(i64::from(d.num_days_from_monday())))
+                                .and_time(self.start.time())
+                        })
+                        .filter(|start| *start >= self.start)
+                        .collect()
+                }
+            };
+            for start in starts {
+                let Some(b) = to_busy(start) else { continue };
+                let ended = rrule.until.is_some_and(|until| b.start > until)
+                    || rrule.count.is_some_and(|count| seen >= count)
+                    || seen >= MAX_OCCURRENCES
+                    || b.start >= to;
+                if ended {
+                    break 'periods;
+                }
+                seen += 1;
+                if b.end > from && !self.exdates.contains(&b.start) {
+                    busy.push(b);
+                }
+            }
+            period += 1;
+        }
+        busy
+    }
+}
+
+/// Parses the events of an iCalendar document. Times without a `TZID` or `Z`
+/// suffix are read in `local`.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use pompom::calendar::parse_ics;
+///
+/// let ics = "BEGIN:VCALENDAR\r\n\
+///            BEGIN:VEVENT\r\n\
+///            UID:standup\r\n\
+///            SUMMARY:Standup\r\n\
+///            DTSTART;TZID=Europe/Berlin:20261019T093000\r\n\
+///            DURATION:PT15M\r\n\
+///            RRULE:FREQ=WEEKLY;BYDAY=MO,WE\r\n\
+///            BEGIN:VALARM\r\n\
+///            DURATION:-PT5M\r\n\
+///            SUMMARY:Reminder\r\n\
+///            END:VALARM\r\n\
+///            END:VEVENT\r\n\
+///            BEGIN:VEVENT\r\n\
+///            SUMMARY:Focus time\r\n\
+///            TRANSP:TRANSPARENT\r\n\
+///            DTSTART:20261019T120000Z\r\n\
+///            DTEND:20261019T130000Z\r\n\
+///            END:VEVENT\r\n\
+///            BEGIN:VEVENT\r\n\
+///            SUMMARY:Exchange meeting\r\n\
+///            DTSTART;TZID=W. Europe Standard Time:20261019T140000\r\n\
+///            DTEND;TZID=W. Europe Standard Time:20261019T150000\r\n\
+///            END:VEVENT\r\n\
+///            END:VCALENDAR\r\n";
+/// let calendar = parse_ics(ics, chrono_tz::UTC);
+/// assert_eq!(calendar.events.len(), 1);
+/// assert_eq!(calendar.skipped.len(), 1);
+///
+/// let from = Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap();
+/// let to = Utc.with_ymd_and_hms(2026, 10, 22, 0, 0, 0).unwrap();
+/// let busy = calendar.events[0].busy_between(from, to);
+/// assert_eq!(busy.len(), 2);
+/// assert_eq!(busy[1].start, Utc.with_ymd_and_hms(2026, 10, 21, 7, 30, 0).unwrap());
+/// assert_eq!(busy[1].end - busy[1].start, chrono::Duration::minutes(15));
+/// assert_eq!(busy[1].label.as_deref(), Some("Standup"));
+/// ```
+///
+/// Occurrences excluded with `EXDATE`, or moved with `RECURRENCE-ID`, only
+/// block time where they end up:
+///
+/// ```
+/// # use chrono::{TimeZone, Utc};
+/// # use pompom::calendar::parse_ics;
+/// let ics = "BEGIN:VEVENT\r\n\
+///            UID:sync\r\n\
+///            DTSTART:20261019T100000Z\r\n\
+///            DURATION:PT30M\r\n\
+///            RRULE:FREQ=DAILY;COUNT=3\r\n\
+///            EXDATE:20261020T100000Z\r\n\
+///            END:VEVENT\r\n\
+///            BEGIN:VEVENT\r\n\
+///            UID:sync\r\n\
+///            RECURRENCE-ID:20261021T100000Z\r\n\
+///            DTSTART:20261021T150000Z\r\n\
+///            DURATION:PT30M\r\n\
+///            END:VEVENT\r\n";
+/// let calendar = parse_ics(ics, chrono_tz::UTC);
+///
+/// let from = Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap();
+/// let to = Utc.with_ymd_and_hms(2026, 10, 22, 0, 0, 0).unwrap();
+/// let mut starts: Vec<_> = calendar
+///     .events
+///     .iter()
+///     .flat_map(|event| event.busy_between(from, to))
+///     .map(|busy| busy.start)
+///     .collect();
+/// starts.sort();
+/// assert_eq!(
+///     starts,
+///     [
+///         Utc.with_ymd_and_hms(2026, 10, 19, 10, 0, 0).unwrap(),
+///         Utc.with_ymd_and_hms(2026, 10, 21, 15, 0, 0).unwrap(),
+///     ]
+/// );
+/// ```
+pub fn parse_ics(s: &str, local: Tz) -> Calendar {
+    let mut calendar = Calendar::default();
+    // UIDs of the events, to find the recurring event of a moved instance.
+    let mut uids = Vec::new();
+    let mut moved = Vec::new();
+    let mut current: Option<EventBuilder> = None;
+    // Components nested in the event, e.g. `VALARM`, whose properties aren't
+    // the event's.
+    let mut depth = 0_usize;
+    for (n, line) in unfold(s).iter().enumerate() {
+        let Some((name_params, value)) = line.split_once(':') else { continue };
+        let name = name_params.split(';').next().unwrap_or_default().to_ascii_uppercase();
+        let param = |key: &str| {
+            name_params.split(';').skip(1).find_map(|p| {
+                let (k, v) = p.split_once('=')?;
+                k.eq_ignore_ascii_case(key).then(|| v.trim_matches('"').to_string())
+            })
+        };
+        let Some(b) = current.as_mut() else {
+            if name == "BEGIN" && value.eq_ignore_ascii_case("VEVENT") {
+                current = Some(EventBuilder { line: n + 1, ..EventBuilder::default() });
+            }
+            continue;
+        };
+        let mut failed = |e: String| {
+            b.error.get_or_insert(e);
+        };
+        match name.as_str() {
+            "BEGIN" => depth += 1,
+            "END" if depth > 0 => depth -= 1,
+            "END" => {
+                let Some(b) = current.take() else { continue };
+                let uid = b.uid.clone();
+                match b.build(local) {
+                    Ok(built) => {
+                        if let (Some(uid), Some(instant)) = (&uid, built.moved) {
+                            moved.push((uid.clone(), instant));
+                        }
+                        if let Some(event) = built.event {
+                            calendar.events.push(event);
+                            uids.push(uid);
+                        }
+                    }
+                    Err(e) => calendar.skipped.push(e),
+                }
+            }
+            _ if depth > 0 => {}
+            "UID" => b.uid = Some(value.to_string()),
+            "SUMMARY" => b.summary = Some(unescape(value)),
+            "DTSTART" => b.start = Some((value.to_string(), param("TZID"))),
+            "DTEND" => b.end = Some((value.to_string(), param("TZID"))),
+            "DURATION" => match parse_duration(value) {
+                Ok(duration) => b.duration = Some(duration),
+                Err(e) => failed(e),
+            },
+            "RRULE" => match parse_rrule(value, local) {
+                Ok(rrule) => b.rrule = Some(rrule),
+                Err(e) => failed(e),
+            },
+            "EXDATE" => {
+                let tzid = param("TZID");
+                b.exdates.extend(value.split(',').map(|v| (v.to_string(), tzid.clone())));
+            }
+            "RECURRENCE-ID" => b.recurrence_id = Some((value.to_string(), param("TZID"))),
+            "TRANSP" => b.free = value.eq_ignore_ascii_case("TRANSPARENT"),
+            "STATUS" => b.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            _ => {}
+        }
+    }
+    for (uid, instant) in moved {
+        let recurring = calendar.events.iter_mut().zip(&uids).filter(|(event, event_uid)| {
+            event.rrule.is_some() && event_uid.as_deref() == Some(uid.as_str())
+        });
+        for (event, _) in recurring {
+            event.exdates.push(instant);
+        }
+    }
+    calendar
+}
+
+#[derive(Debug, Default)]
+struct EventBuilder {
+    /// Line of its `BEGIN:VEVENT`.
+    line: usize,
+    uid: Option<String>,
+    summary: Option<String>,
+    start: Option<(String, Option<String>)>,
+    end: Option<(String, Option<String>)>,
+    duration: Option<Duration>,
+    rrule: Option<RRule>,
+    exdates: Vec<(String, Option<String>)>,
+    recurrence_id: Option<(String, Option<String>)>,
+    free: bool,
+    cancelled: bool,
+    /// The first property that couldn't be read.
+    error: Option<String>,
+}
+
+/// What a `VEVENT` adds to the calendar.
+struct Built {
+    /// `None` for events that never block time.
+    event: Option<Event>,
+    /// The occurrence of a recurring event that this one moves or cancels.
+    moved: Option<DateTime<Utc>>,
+}
+
+impl EventBuilder {
+    fn build(self, local: Tz) -> Result<Built, String> {
+        let name = match &self.summary {
+            Some(summary) => format!("event `{summary}`"),
+            None => String::from("event"),
+        };
+        let skipped = |e: String| format!("skipped the {name} at line {}: {e}", self.line);
+        if let Some(e) = self.error {
+            return Err(skipped(e));
+        }
+        let utc = |dt: NaiveDateTime, tz: Tz| {
+            tz.from_local_datetime(&dt).earliest().map(|d| d.with_timezone(&Utc))
+        };
+        let moved = match &self.recurrence_id {
+            Some((value, tzid)) => match parse_datetime(value, tzid.as_deref(), local) {
+                Some(parsed) => parsed.map(|(dt, tz)| utc(dt, tz)).map_err(skipped)?,
+                // An all-day event was moved, which never blocked time.
+                None => None,
+            },
+            None => None,
+        };
+        let unblocked = Built { event: None, moved };
+        if self.free || self.cancelled {
+            return Ok(unblocked);
+        }
+        let Some((start, tzid)) = self.start else { return Ok(unblocked) };
+        let Some(start) = parse_datetime(&start, tzid.as_deref(), local) else {
+            return Ok(unblocked);
+        };
+        let (start, tz) = start.map_err(skipped)?;
+        let duration = match (self.duration, self.end) {
+            (Some(duration), _) => duration,
+            (None, Some((end, tzid))) => match parse_datetime(&end, tzid.as_deref(), local) {
+                Some(end) => {
+                    let (end, end_tz) = end.map_err(skipped)?;
+                    match (utc(start, tz), utc(end, end_tz)) {
+                        (Some(s), Some(e)) => e - s,
+                        _ => Duration::zero(),
+                    }
+                }
+                None => Duration::zero(),
+            },
+            (None, None) => Duration::zero(),
+        };
+        let mut exdates = Vec::new();
+        for (value, tzid) in &self.exdates {
+            let exdate = match parse_datetime(value, tzid.as_deref(), local) {
+                Some(exdate) => exdate.map_err(skipped)?,
+                // An all-day date excludes the occurrence starting that day.
+                None => NaiveDate::parse_from_str(value, "%Y%m%d")
+                    .map(|date| (date.and_time(start.time()), tz))
+                    .map_err(|e| skipped(format!("EXDATE `{value}`: {e}")))?,
+            };
+            exdates.extend(utc(exdate.0, exdate.1));
+        }
+        let event =
+            Event { summary: self.summary, start, tz, duration, rrule: self.rrule, exdates };
+        Ok(Built { event: Some(event), moved })
+    }
+}
+
+/// `None` for all-day dates, which don't block time.
+fn parse_datetime(
+    value: &str,
+    tzid: Option<&str>,
+    local: Tz,
+) -> Option<Result<(NaiveDateTime, Tz), String>> {
+    if value.len() == 8 {
+        return None;
+    }
+    let (value, tz) = match (value.strip_suffix('Z'), tzid) {
+        (Some(value), _) => (value, Ok(Tz::UTC)),
+        (None, Some(tzid)) => {
+            (value, tzid.parse::<Tz>().map_err(|_e| format!("unknown TZID `{tzid}`")))
+        }
+        (None, None) => (value, Ok(local)),
+    };
+    let parsed = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .map_err(|e| format!("date-time `{value}`: {e}"));
+    Some(parsed.and_then(|dt| tz.map(|tz| (dt, tz))))
+}
+
+/// RFC 5545 durations such as `PT1H30M`, `P1D` or `P1W`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let err = || format!("duration `{value}`");
+    let rest = value.trim_start_matches('+').strip_prefix('P').ok_or_else(err)?;
+    let mut total = Duration::zero();
+    let mut digits = String::new();
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => digits.push(c),
+            'T' => {}
+            'W' | 'D' | 'H' | 'M' | 'S' => {
+                let n: i64 = digits.parse().map_err(|_e| err())?;
+                total = total
+                    + match c {
+                        'W' => Duration::weeks(n),
+                        'D' => Duration::days(n),
+                        'H' => Duration::hours(n),
+                        'M' => Duration::minutes(n),
+                        _ => Duration::seconds(n),
+                    };
+                digits.clear();
+            }
+            _ => return Err(err()),
+        }
+    }
+    Ok(total)
+}
+
+fn parse_rrule(value: &str, local: Tz) -> Result<RRule, String> {
+    let mut rrule =
+        RRule { freq: Freq::Daily, interval: 1, count: None, until: None, by_day: Vec::new() };
+    let mut freq = None;
+    for part in value.split(';') {
+        let Some((key, v)) = part.split_once('=') else { continue };
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                freq = Some(match v.to_ascii_uppercase().as_str() {
+                    "DAILY" => Freq::Daily,
+                    "WEEKLY" => Freq::Weekly,
+                    other => return Err(format!("unsupported recurrence FREQ={other}")),
+                });
+            }
+            "INTERVAL" => rrule.interval = v.parse().map_err(|_e| format!("INTERVAL={v}"))?,
+            "COUNT" => rrule.count = Some(v.parse().map_err(|_e| format!("COUNT={v}"))?),
+            "UNTIL" => {
+                rrule.until = match parse_datetime(v, None, local) {
+                    Some(until) => {
+                        let (dt, tz) = until?;
+                        tz.from_local_datetime(&dt).earliest().map(|d| d.with_timezone(&Utc))
+                    }
+                    // An all-day UNTIL includes that whole day.
+                    None => NaiveDate::parse_from_str(v, "%Y%m%d")
+                        .ok()
+                        .and_then(|d| d.and_hms_opt(23, 59, 59))
+                        .map(|dt| Utc.from_utc_datetime(&dt)),
+                };
+            }
+            "BYDAY" => {
+                rrule.by_day = v
+                    .split(',')
+                    .map(|d| {
+                        // Drop ordinals like `1MO`, which only apply to monthly rules.
+                        let d = d.trim_start_matches(|c: char| {
+                            c.is_ascii_digit() || c == '-' || c == '+'
+                        });
+                        match d.to_ascii_uppercase().as_str() {
+                            "MO" => Ok(Weekday::Mon),
+                            "TU" => Ok(Weekday::Tue),
+                            "WE" => Ok(Weekday::Wed),
+                            "TH" => Ok(Weekday::Thu),
+                            "FR" => Ok(Weekday::Fri),
+                            "SA" => Ok(Weekday::Sat),
+                            "SU" => Ok(Weekday::Sun),
+                            other => Err(format!("BYDAY={other}")),
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            _ => {}
+        }
+    }
+    rrule.freq = freq.ok_or_else(|| String::from("recurrence rule without FREQ"))?;
+    Ok(rrule)
+}
+
+/// Joins folded content lines, which continue with a leading space or tab.
+fn unfold(s: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in s.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// Expands a leading `~/` to the home directory.
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Reads the events of every calendar file listed in the config.
+pub fn load_events(config: &Config, local: Tz) -> miette::Result<Vec<Event>> {
+    let mut events = Vec::new();
+    for path in &config.calendars {
+        let path = expand_home(path);
+        let s = std::fs::read_to_string(&path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to read calendar {}", path.display()))?;
+        let calendar = parse_ics(&s, local);
+        for skipped in &calendar.skipped {
+            eprintln!("Warning: calendar {}: {skipped}", path.display());
+        }
+        events.extend(calendar.events);
+    }
+    Ok(events)
+}
+
+/// Blocked ranges and calendar events on `days` dates from `from`, sorted by start.
+pub fn load_busy(config: &Config, tz: Tz, from: NaiveDate, days: i64) -> miette::Result<Vec<Busy>> {
+    let start = tz.from_local_datetime(&from.and_hms_opt(0, 0, 0).unwrap_or_default()).earliest();
+    let start = start.map_or_else(Utc::now, |d| d.with_timezone(&Utc));
+    let end = start + Duration::days(days);
+
+    let mut busy: Vec<Busy> =
+        config.blocked.iter().flat_map(|range| range.occurrences(tz, from, days)).collect();
+    for event in load_events(config, tz)? {
+        busy.extend(event.busy_between(start, end));
+    }
+    busy.sort_by_key(|b| b.start);
+    Ok(busy)
+}
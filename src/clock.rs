@@ -0,0 +1,95 @@
+//! Where the timer gets the time from: the system clock, or a
+//! [`ManualClock`] that only moves when told to, for tests.
+
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::watch;
+
+use crate::HumanDuration;
+
+/// A wait started by [`Clock::sleep_until`].
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Resolves once [`Clock::now`] has reached `deadline`, at once if it
+    /// already has.
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> Sleep;
+}
+
+/// The time of the system, waited for with [`tokio::time::sleep`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> Sleep {
+        let wait = (deadline - Utc::now()).to_std().unwrap_or_default();
+        Box::pin(tokio::time::sleep(wait))
+    }
+}
+
+/// A clock that stands still until [`ManualClock::advance`]d. Clones share
+/// the same time.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use pompom::{clock::{Clock, ManualClock}, HumanDuration};
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let start = Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+/// let clock = ManualClock::new(start);
+/// let alarm = tokio::spawn(clock.sleep_until(start + chrono::Duration::minutes(25)));
+///
+/// clock.advance(HumanDuration::from_mins(24));
+/// tokio::task::yield_now().await;
+/// assert!(!alarm.is_finished());
+/// clock.advance(HumanDuration::from_mins(1));
+/// alarm.await.unwrap();
+/// assert_eq!(clock.now(), start + chrono::Duration::minutes(25));
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<watch::Sender<DateTime<Utc>>>,
+}
+
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now: Arc::new(watch::channel(now).0) }
+    }
+
+    /// Moves the time forward by `duration`, waking whatever it was slept
+    /// until.
+    pub fn advance(&self, duration: HumanDuration) {
+        self.now.send_modify(|now| *now += duration.to_chrono());
+    }
+
+    /// Sets the time, e.g. back to a known start.
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.now.send_replace(now);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.borrow()
+    }
+
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> Sleep {
+        let mut now = self.now.subscribe();
+        Box::pin(async move {
+            while *now.borrow_and_update() < deadline {
+                if now.changed().await.is_err() {
+                    // Dropped with every clone, so it won't get there.
+                    std::future::pending::<()>().await;
+                }
+            }
+        })
+    }
+}
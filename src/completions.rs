@@ -0,0 +1,152 @@
+//! `pompom completions <shell>` and `pompom man`, rendered from the clap
+//! command tree.
+//!
+//! The bash, zsh and fish scripts also complete `--task` and `--profile`
+//! with the queued tasks and the profiles of the config, which they ask for
+//! with the hidden `pompom complete tasks` and `pompom complete profiles`.
+
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use clap::CommandFactory;
+use clap_complete::Shell;
+use miette::{IntoDiagnostic, WrapErr};
+
+use crate::{config::Config, db::Database, PomoFocusCli};
+
+/// What `pompom complete` lists, one `value<TAB>description` per line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Candidates {
+    /// Queued task numbers, and `@source:N` for open items of the sources.
+    Tasks,
+    /// Profiles of the config.
+    Profiles,
+}
+
+/// `$ pompom completions <shell>`
+pub fn run_command(shell: Shell) -> miette::Result<()> {
+    let mut cmd = PomoFocusCli::command();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut cmd, "pompom", &mut script);
+    let script = String::from_utf8_lossy(&script);
+    let script = match shell {
+        Shell::Bash => format!("{script}\n{BASH}"),
+        Shell::Zsh => with_zsh_candidates(&script),
+        Shell::Fish => format!("{script}\n{FISH}"),
+        _ => script.into_owned(),
+    };
+    io::stdout().write_all(script.as_bytes()).into_diagnostic()
+}
+
+/// Calls the zsh functions below for the values of `--task` and
+/// `--profile`, which clap leaves without an action.
+fn with_zsh_candidates(script: &str) -> String {
+    let script = script
+        .replace(":TASK: '", ":TASK:_pompom_tasks'")
+        .replace(":PROFILE: '", ":PROFILE:_pompom_profiles'");
+    // Defined first, as an autoloaded script runs `_pompom` at its end.
+    match script.split_once('\n') {
+        Some((compdef, rest)) => format!("{compdef}\n{ZSH}\n{rest}"),
+        None => script,
+    }
+}
+
+const BASH: &str = r#"_pompom_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}" what
+    case "$prev" in
+        --task) what=tasks ;;
+        --profile|-p) what=profiles ;;
+        *) _pompom "$@"; return ;;
+    esac
+    local IFS=$'\n'
+    COMPREPLY=($(compgen -W "$(pompom complete "$what" 2>/dev/null | cut -f1)" -- "$cur"))
+}
+complete -F _pompom_dynamic -o nosort -o bashdefault -o default pompom
+"#;
+
+const ZSH: &str = r#"_pompom_candidates() {
+    local line
+    local -a candidates
+    for line in ${(f)"$(pompom complete $1 2>/dev/null)"}; do
+        candidates+=("${${line%%$'\t'*}//:/\\:}:${line#*$'\t'}")
+    done
+    _describe $1 candidates
+}
+_pompom_tasks() { _pompom_candidates tasks }
+_pompom_profiles() { _pompom_candidates profiles }
+"#;
+
+const FISH: &str = r#"complete -c pompom -n "__fish_seen_subcommand_from start" -l task -r -f -a "(pompom complete tasks 2>/dev/null)"
+complete -c pompom -n "__fish_use_subcommand" -s p -l profile -r -f -a "(pompom complete profiles 2>/dev/null)"
+"#;
+
+/// `$ pompom man`, which prints the page of `pompom`, or writes one page
+/// per command to `out_dir`.
+pub fn man(out_dir: Option<&Path>) -> miette::Result<()> {
+    let cmd = PomoFocusCli::command();
+    let Some(out_dir) = out_dir else {
+        return clap_mangen::Man::new(cmd).render(&mut io::stdout()).into_diagnostic();
+    };
+    std::fs::create_dir_all(out_dir).into_diagnostic()?;
+    write_pages(&cmd, "pompom", out_dir)
+}
+
+/// Writes the page of `cmd`, e.g. `pompom-task-add.1`, and of its
+/// subcommands.
+fn write_pages(cmd: &clap::Command, name: &str, out_dir: &Path) -> miette::Result<()> {
+    let path = out_dir.join(format!("{name}.1"));
+    let mut page = Vec::new();
+    clap_mangen::Man::new(cmd.clone().name(name.to_string()).version(env!("CARGO_PKG_VERSION")))
+        .render(&mut page)
+        .into_diagnostic()?;
+    std::fs::write(&path, page)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+    println!("Wrote {}", path.display());
+    for sub in cmd.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        write_pages(sub, &format!("{name}-{}", sub.get_name()), out_dir)?;
+    }
+    Ok(())
+}
+
+/// `$ pompom complete <tasks|profiles>`, which lists nothing rather than
+/// failing, so errors never end up in a completion.
+pub fn candidates(what: Candidates) {
+    let lines = match what {
+        Candidates::Tasks => tasks(),
+        Candidates::Profiles => Config::load()
+            .map(|config| {
+                let profiles = config.profiles.into_iter();
+                profiles
+                    .map(|(name, p)| {
+                        format!("{name}\t{}/{}/{}", p.work, p.short_break, p.long_break)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    for line in lines {
+        println!("{line}");
+    }
+}
+
+fn tasks() -> Vec<String> {
+    let mut lines: Vec<String> = Database::open_default()
+        .and_then(|db| db.pending_tasks())
+        .map(|tasks| {
+            tasks.into_iter().filter_map(|t| Some(format!("{}\t{}", t.id?, t.title))).collect()
+        })
+        .unwrap_or_default();
+    let sources = Config::load().map(|config| config.sources.all()).unwrap_or_default();
+    for source in sources {
+        let Ok(items) = source.open_items() else {
+            continue;
+        };
+        lines.extend(
+            items.into_iter().map(|item| format!("@{}:{}\t{}", source.name(), item.id, item.title)),
+        );
+    }
+    lines
+}
@@ -0,0 +1,271 @@
+//! User configuration read from `$XDG_CONFIG_HOME/pompom/config.toml`,
+//! falling back to `~/.config/pompom/config.toml`.
+//!
+//! ```toml
+//! profile = "deep"
+//! calendars = ["~/calendars/work.ics"]
+//! reflect = true
+//!
+//! [profiles.deep]
+//! work = "50m"
+//! short_break = "10m"
+//! long_break = "30m"
+//! cycles = 3
+//!
+//! [sources]
+//! todo = "~/todo/todo.txt"
+//! org = "~/org/work.org"
+//!
+//! [hooks]
+//! work_start = ["dunstctl set-paused true"]
+//! break_start = ["dunstctl set-paused false"]
+//!
+//! [api]
+//! port = 7070
+//! token = "a long random string"
+//!
+//! [metrics]
+//! port = 9464
+//!
+//! [working_hours]
+//! start = "09:00"
+//! end = "17:30"
+//! days = ["Mon", "Tue", "Wed", "Thu", "Fri"]
+//! timezone = "Europe/Berlin"
+//! strict = false
+//!
+//! [[schedules]]
+//! at = "09:30"
+//! days = ["Mon", "Tue", "Wed", "Thu", "Fri"]
+//! timezone = "Europe/Berlin"
+//!
+//! [[blocked]]
+//! start = "12:00"
+//! end = "13:00"
+//! label = "Lunch"
+//!
+//! [[webhooks]]
+//! url = "http://localhost:8080/notify"
+//! events = ["work_start", "set_end"]
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use miette::{IntoDiagnostic, WrapErr};
+use serde::{Deserialize, Serialize};
+use toml_edit::{ArrayOfTables, Document, Item, Table};
+
+use crate::{
+    api::ApiConfig,
+    error::PomodoroError,
+    hooks::Hooks,
+    metrics::MetricsConfig,
+    plan::BlockedRange,
+    progress::ProgressConfig,
+    reminders::Reminders,
+    schedule::{Schedule, WorkingHours},
+    source::TaskSources,
+    theme::Theme,
+    webhook::Webhook,
+    HumanDuration, DEFAULT_LONG_BREAK_TIME, DEFAULT_SHORT_BREAK_TIME, DEFAULT_WORK_CYCLES,
+    DEFAULT_WORK_TIME,
+};
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Name of the profile used when `--profile` isn't given.
+    pub profile: Option<String>,
+    /// Local iCalendar files whose events count as busy time.
+    pub calendars: Vec<PathBuf>,
+    /// Ask for a short reflection note after each work phase.
+    pub reflect: bool,
+    pub profiles: BTreeMap<String, Profile>,
+    /// `todo.txt` and Org files to pick tasks from, e.g. `--task @todo:3`.
+    pub sources: TaskSources,
+    /// Commands run on state transitions, see [`crate::hooks`].
+    pub hooks: Hooks,
+    /// Warn, or refuse when `strict`, before starting outside these hours.
+    pub working_hours: Option<WorkingHours>,
+    /// Start times picked up by `pompom daemon`.
+    pub schedules: Vec<Schedule>,
+    /// Meetings and other time that `pompom plan` schedules around.
+    pub blocked: Vec<BlockedRange>,
+    /// Opt-in HTTP API for controlling the timer, see [`crate::api`].
+    pub api: Option<ApiConfig>,
+    /// Prometheus metrics served by `pompom daemon`, see [`crate::metrics`].
+    pub metrics: Option<MetricsConfig>,
+    /// URLs notified of transitions, see [`crate::webhook`].
+    pub webhooks: Vec<Webhook>,
+    /// Colors and markers of the phases, see [`crate::theme`].
+    pub theme: Theme,
+    /// Templates of the progress bar, see [`crate::progress`].
+    pub progress: ProgressConfig,
+    /// How phases are reminded of, see [`crate::reminders`].
+    pub reminders: Reminders,
+}
+
+impl Config {
+    /// Default location of the config file.
+    pub fn path() -> PathBuf {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME").map_or_else(
+            || PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".config"),
+            PathBuf::from,
+        );
+        config_dir.join("pompom").join("config.toml")
+    }
+
+    /// Loads the config from [`Config::path`], or the defaults if there is no file yet.
+    pub fn load() -> miette::Result<Self> {
+        Self::load_from(Self::path())
+    }
+
+    pub fn load_from(path: impl AsRef<Path>) -> miette::Result<Self> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(s) => toml::from_str(&s)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to parse config file {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to read config file {}", path.display())),
+        }
+    }
+
+    pub fn profile(&self, name: &str) -> miette::Result<Profile> {
+        self.profiles.get(name).cloned().ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            let known = if known.is_empty() {
+                String::from("Add one as a [profiles.<name>] table in the config")
+            } else {
+                format!("Known profiles: {}", known.join(", "))
+            };
+            PomodoroError::UnknownProfile { name: name.to_string(), known }.into()
+        })
+    }
+
+    /// Writes the whole config to [`Config::path`]. This rewrites the file,
+    /// dropping its comments and layout, see [`Config::save_schedules`] for
+    /// an edit that keeps them.
+    pub fn save(&self) -> miette::Result<()> {
+        self.save_to(Self::path())
+    }
+
+    pub fn save_to(&self, path: impl AsRef<Path>) -> miette::Result<()> {
+        // Going through a `Value` puts plain values before tables, which
+        // writing the struct directly fails on, e.g. for `schedules = []`.
+        let value = toml::Value::try_from(self).into_diagnostic()?;
+        let s = toml::to_string_pretty(&value).into_diagnostic()?;
+        write(path.as_ref(), &s)
+    }
+
+    /// Writes `schedules` to the config file, leaving the rest of it,
+    /// comments included, as it is.
+    pub fn save_schedules(&self) -> miette::Result<()> {
+        self.save_schedules_to(Self::path())
+    }
+
+    pub fn save_schedules_to(&self, path: impl AsRef<Path>) -> miette::Result<()> {
+        edit(path.as_ref(), |doc| {
+            if self.schedules.is_empty() {
+                doc.remove("schedules");
+                return Ok(());
+            }
+            let mut schedules = ArrayOfTables::new();
+            for schedule in &self.schedules {
+                schedules.push(table(schedule)?);
+            }
+            doc.insert("schedules", Item::ArrayOfTables(schedules));
+            Ok(())
+        })
+    }
+
+    /// Adds `profile` to the config file as `[profiles.<name>]`, replacing
+    /// one of that name, and leaving the rest of the file as it is.
+    pub fn save_profile(name: &str, profile: &Profile) -> miette::Result<()> {
+        Self::save_profile_to(Self::path(), name, profile)
+    }
+
+    pub fn save_profile_to(
+        path: impl AsRef<Path>,
+        name: &str,
+        profile: &Profile,
+    ) -> miette::Result<()> {
+        edit(path.as_ref(), |doc| {
+            let profiles = doc.entry("profiles").or_insert_with(|| {
+                let mut profiles = Table::new();
+                profiles.set_implicit(true);
+                Item::Table(profiles)
+            });
+            let profiles = profiles
+                .as_table_mut()
+                .ok_or_else(|| miette::miette!("`profiles` in the config isn't a table"))?;
+            profiles.insert(name, Item::Table(table(profile)?));
+            Ok(())
+        })
+    }
+}
+
+/// Applies `change` to the config file at `path`, or to an empty one if
+/// there is no file yet.
+fn edit(
+    path: &Path,
+    change: impl FnOnce(&mut Document) -> miette::Result<()>,
+) -> miette::Result<()> {
+    let s = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to read config file {}", path.display()))
+        }
+    };
+    let mut doc: Document = s
+        .parse()
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to parse config file {}", path.display()))?;
+    change(&mut doc)?;
+    write(path, &doc.to_string())
+}
+
+/// `value` as a table of plain values, the way `toml` writes it.
+fn table(value: &impl Serialize) -> miette::Result<Table> {
+    let s = toml::to_string(value).into_diagnostic()?;
+    let doc: Document = s.parse().into_diagnostic()?;
+    Ok(doc.as_table().clone())
+}
+
+fn write(path: &Path, s: &str) -> miette::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).into_diagnostic()?;
+    }
+    std::fs::write(path, s)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to write config file {}", path.display()))
+}
+
+/// Phase lengths and cycle count of a pomodoro set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub work: HumanDuration,
+    pub short_break: HumanDuration,
+    pub long_break: HumanDuration,
+    pub cycles: u16,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            work: DEFAULT_WORK_TIME,
+            short_break: DEFAULT_SHORT_BREAK_TIME,
+            long_break: DEFAULT_LONG_BREAK_TIME,
+            cycles: DEFAULT_WORK_CYCLES,
+        }
+    }
+}
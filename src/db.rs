@@ -0,0 +1,532 @@
+//! Session history persisted in a local `SQLite` database.
+//!
+//! The database lives at `$XDG_DATA_HOME/pompom/pompom.db`, falling back to
+//! `~/.local/share/pompom/pompom.db`.
+
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::error::PomodoroError;
+
+/// Bumped whenever [`Database::migrate`] learns a new schema step.
+pub const SCHEMA_VERSION: i32 = 5;
+
+/// What kind of timer a [`Session`] was recorded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    Work,
+    ShortBreak,
+    LongBreak,
+    Timer,
+    Stopwatch,
+}
+
+impl SessionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionKind::Work => "work",
+            SessionKind::ShortBreak => "short_break",
+            SessionKind::LongBreak => "long_break",
+            SessionKind::Timer => "timer",
+            SessionKind::Stopwatch => "stopwatch",
+        }
+    }
+}
+
+impl fmt::Display for SessionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SessionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "work" => Ok(SessionKind::Work),
+            "short_break" => Ok(SessionKind::ShortBreak),
+            "long_break" => Ok(SessionKind::LongBreak),
+            "timer" => Ok(SessionKind::Timer),
+            "stopwatch" => Ok(SessionKind::Stopwatch),
+            other => Err(format!("unknown session kind `{other}`")),
+        }
+    }
+}
+
+/// How a [`Session`] ended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionOutcome {
+    /// Ran until its time was up.
+    #[default]
+    Completed,
+    /// Ended early with `skip`, the set carried on.
+    Skipped,
+    /// Ended early by stopping the set or the timer.
+    Interrupted,
+}
+
+impl SessionOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionOutcome::Completed => "completed",
+            SessionOutcome::Skipped => "skipped",
+            SessionOutcome::Interrupted => "interrupted",
+        }
+    }
+}
+
+impl fmt::Display for SessionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SessionOutcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "completed" => Ok(SessionOutcome::Completed),
+            "skipped" => Ok(SessionOutcome::Skipped),
+            "interrupted" => Ok(SessionOutcome::Interrupted),
+            other => Err(format!("unknown session outcome `{other}`")),
+        }
+    }
+}
+
+/// A finished timer, stopwatch or pomodoro phase.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// Row id in this database. Not exported, see [`Session::uuid`].
+    #[serde(skip)]
+    pub id: Option<i64>,
+    /// Stable across machines, so imports can tell sessions apart.
+    pub uuid: String,
+    pub kind: SessionKind,
+    pub label: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// Title of the task a work phase was spent on.
+    pub task: Option<String>,
+    /// Reflection written after a work phase.
+    pub note: Option<String>,
+    /// Times the phase was paused.
+    #[serde(default)]
+    pub interruptions: u32,
+    /// Older histories only recorded completed phases.
+    #[serde(default)]
+    pub outcome: SessionOutcome,
+}
+
+impl Session {
+    pub fn new(kind: SessionKind, started_at: DateTime<Utc>, ended_at: DateTime<Utc>) -> Self {
+        Self {
+            id: None,
+            uuid: new_uuid(),
+            kind,
+            label: None,
+            started_at,
+            ended_at,
+            task: None,
+            note: None,
+            interruptions: 0,
+            outcome: SessionOutcome::Completed,
+        }
+    }
+
+    pub fn with_label(mut self, label: Option<String>) -> Self {
+        self.label = label;
+        self
+    }
+
+    pub fn with_task(mut self, task: Option<String>) -> Self {
+        self.task = task;
+        self
+    }
+
+    pub fn with_note(mut self, note: Option<String>) -> Self {
+        self.note = note;
+        self
+    }
+
+    pub fn with_interruptions(mut self, interruptions: u32) -> Self {
+        self.interruptions = interruptions;
+        self
+    }
+
+    pub fn with_outcome(mut self, outcome: SessionOutcome) -> Self {
+        self.outcome = outcome;
+        self
+    }
+
+    fn from_row(row: &Row<'_>) -> Result<Self> {
+        let kind: String = row.get(1)?;
+        let outcome: String = row.get(9)?;
+        Ok(Self {
+            id: row.get(0)?,
+            kind: kind.parse().map_err(|e: String| {
+                rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, e.into())
+            })?,
+            label: row.get(2)?,
+            started_at: parse_timestamp(row, 3)?,
+            ended_at: parse_timestamp(row, 4)?,
+            task: row.get(5)?,
+            note: row.get(6)?,
+            uuid: row.get(7)?,
+            interruptions: row.get(8)?,
+            outcome: outcome.parse().map_err(|e: String| {
+                rusqlite::Error::FromSqlConversionFailure(9, rusqlite::types::Type::Text, e.into())
+            })?,
+        })
+    }
+}
+
+/// A random (version 4) UUID.
+pub fn new_uuid() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+fn parse_timestamp(row: &Row<'_>, idx: usize) -> Result<DateTime<Utc>> {
+    let s: String = row.get(idx)?;
+    DateTime::parse_from_rfc3339(&s).map(|d| d.with_timezone(&Utc)).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+/// A queued piece of work, estimated in pomodoros.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    /// Row id in this database. Not exported, see [`Task::uuid`].
+    #[serde(skip)]
+    pub id: Option<i64>,
+    pub uuid: String,
+    pub title: String,
+    pub estimate: u16,
+    pub done: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Task {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            uuid: new_uuid(),
+            title: title.into(),
+            estimate: 1,
+            done: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_estimate(mut self, estimate: u16) -> Self {
+        self.estimate = estimate;
+        self
+    }
+
+    fn from_row(row: &Row<'_>) -> Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            estimate: row.get(2)?,
+            done: row.get(3)?,
+            created_at: parse_timestamp(row, 4)?,
+            uuid: row.get(5)?,
+        })
+    }
+}
+
+/// Default location of the history database.
+pub fn default_path() -> PathBuf {
+    let data_dir = std::env::var_os("XDG_DATA_HOME").map_or_else(
+        || PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".local/share"),
+        PathBuf::from,
+    );
+    data_dir.join("pompom").join("pompom.db")
+}
+
+#[derive(Debug)]
+pub struct Database {
+    conn: Connection,
+}
+
+impl Database {
+    /// Opens the database at [`default_path`], creating it if needed.
+    pub fn open_default() -> Result<Self> {
+        let path = default_path();
+        if let Some(dir) = path.parent() {
+            // Let `Connection::open` report the failure if the directory is unusable.
+            let _ = std::fs::create_dir_all(dir);
+        }
+        Self::open(path)
+    }
+
+    /// [`Database::open_default`] for commands, failing with a diagnostic
+    /// that says where the file is and what to do about it.
+    pub fn open_history() -> std::result::Result<Self, PomodoroError> {
+        Self::open_default()
+            .map_err(|source| PomodoroError::History { path: default_path(), source })
+    }
+
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        let db = Self { conn };
+        db.migrate()?;
+        Ok(db)
+    }
+
+    /// Brings the schema up to [`SCHEMA_VERSION`] using `PRAGMA user_version`.
+    ///
+    /// Each step bumps the version in the same transaction as its changes, so
+    /// one that fails halfway is retried from its start on the next open.
+    fn migrate(&self) -> Result<()> {
+        let version: i32 = self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version < 1 {
+            self.in_transaction(|db| {
+                db.conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS sessions (
+                        id          INTEGER PRIMARY KEY,
+                        kind        TEXT NOT NULL,
+                        label       TEXT,
+                        started_at  TEXT NOT NULL,
+                        ended_at    TEXT NOT NULL
+                    );
+                    PRAGMA user_version = 1;",
+                )
+            })?;
+        }
+        if version < 2 {
+            self.in_transaction(|db| {
+                db.conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS tasks (
+                        id          INTEGER PRIMARY KEY,
+                        title       TEXT NOT NULL,
+                        estimate    INTEGER NOT NULL DEFAULT 1,
+                        done        INTEGER NOT NULL DEFAULT 0,
+                        created_at  TEXT NOT NULL
+                    );
+                    PRAGMA user_version = 2;",
+                )
+            })?;
+        }
+        if version < 3 {
+            self.in_transaction(|db| {
+                db.conn.execute_batch(
+                    "ALTER TABLE sessions ADD COLUMN task TEXT;
+                    ALTER TABLE sessions ADD COLUMN note TEXT;
+                    PRAGMA user_version = 3;",
+                )
+            })?;
+        }
+        if version < 4 {
+            self.in_transaction(Self::add_uuids)?;
+        }
+        if version < 5 {
+            self.in_transaction(|db| {
+                db.conn.execute_batch(
+                    "ALTER TABLE sessions ADD COLUMN outcome TEXT NOT NULL DEFAULT 'completed';
+                    PRAGMA user_version = 5;",
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Schema step 4: a uuid for every session and task, so they can be
+    /// matched up across machines.
+    fn add_uuids(&self) -> Result<()> {
+        self.conn.execute_batch(
+            "ALTER TABLE sessions ADD COLUMN uuid TEXT;
+            ALTER TABLE sessions ADD COLUMN interruptions INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE tasks ADD COLUMN uuid TEXT;",
+        )?;
+        for table in ["sessions", "tasks"] {
+            let ids: Vec<i64> = self
+                .conn
+                .prepare(&format!("SELECT id FROM {table} WHERE uuid IS NULL"))?
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<_>>()?;
+            for id in ids {
+                self.conn.execute(
+                    &format!("UPDATE {table} SET uuid = ?1 WHERE id = ?2"),
+                    (new_uuid(), id),
+                )?;
+            }
+        }
+        self.conn.execute_batch(
+            "CREATE UNIQUE INDEX IF NOT EXISTS sessions_uuid ON sessions (uuid);
+            CREATE UNIQUE INDEX IF NOT EXISTS tasks_uuid ON tasks (uuid);
+            PRAGMA user_version = 4;",
+        )
+    }
+
+    /// Runs `f` in a transaction that is rolled back if it fails.
+    pub fn in_transaction<T>(&self, f: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("BEGIN")?;
+        match f(self) {
+            Ok(value) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(value)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// The schema version of the file, [`SCHEMA_VERSION`] unless a newer
+    /// pompom wrote it.
+    pub fn schema_version(&self) -> Result<i32> {
+        self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+    }
+
+    /// Runs `f` in a transaction that is always rolled back.
+    pub fn rolled_back<T>(&self, f: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("BEGIN")?;
+        let result = f(self);
+        self.conn.execute_batch("ROLLBACK")?;
+        result
+    }
+
+    pub fn insert_session(&self, session: &Session) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO sessions
+             (kind, label, started_at, ended_at, task, note, uuid, interruptions, outcome)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            (
+                session.kind.as_str(),
+                &session.label,
+                session.started_at.to_rfc3339(),
+                session.ended_at.to_rfc3339(),
+                &session.task,
+                &session.note,
+                &session.uuid,
+                session.interruptions,
+                session.outcome.as_str(),
+            ),
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// All sessions, oldest first.
+    ///
+    /// ```
+    /// use chrono::Utc;
+    /// use pompom::db::{Database, Session, SessionKind};
+    ///
+    /// let db = Database::open_in_memory().unwrap();
+    /// let now = Utc::now();
+    /// let session = Session::new(SessionKind::Timer, now, now).with_label(Some("tea".into()));
+    /// db.insert_session(&session).unwrap();
+    /// let sessions = db.sessions().unwrap();
+    /// assert_eq!(sessions.len(), 1);
+    /// assert_eq!(sessions[0].label.as_deref(), Some("tea"));
+    /// ```
+    pub fn sessions(&self) -> Result<Vec<Session>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, kind, label, started_at, ended_at, task, note, uuid, interruptions,
+                    outcome
+             FROM sessions ORDER BY started_at",
+        )?;
+        let sessions = stmt.query_map([], Session::from_row)?;
+        sessions.collect()
+    }
+
+    pub fn session_by_uuid(&self, uuid: &str) -> Result<Option<Session>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, kind, label, started_at, ended_at, task, note, uuid, interruptions,
+                    outcome
+             FROM sessions WHERE uuid = ?1",
+        )?;
+        let mut sessions = stmt.query_map([uuid], Session::from_row)?;
+        sessions.next().transpose()
+    }
+
+    /// Returns `false` if there is no session with `uuid`.
+    pub fn set_session_note(&self, uuid: &str, note: &str) -> Result<bool> {
+        let n = self.conn.execute("UPDATE sessions SET note = ?1 WHERE uuid = ?2", (note, uuid))?;
+        Ok(n > 0)
+    }
+
+    pub fn insert_task(&self, task: &Task) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO tasks (title, estimate, done, created_at, uuid) VALUES (?1, ?2, ?3, ?4, ?5)",
+            (&task.title, task.estimate, task.done, task.created_at.to_rfc3339(), &task.uuid),
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Tasks not yet done, in the order they were queued.
+    ///
+    /// ```
+    /// use pompom::db::{Database, Task};
+    ///
+    /// let db = Database::open_in_memory().unwrap();
+    /// let id = db.insert_task(&Task::new("Write report").with_estimate(2)).unwrap();
+    /// db.insert_task(&Task::new("Review PR")).unwrap();
+    /// db.set_task_done(id, true).unwrap();
+    /// let pending = db.pending_tasks().unwrap();
+    /// assert_eq!(pending.len(), 1);
+    /// assert_eq!(pending[0].title, "Review PR");
+    /// ```
+    pub fn pending_tasks(&self) -> Result<Vec<Task>> {
+        self.query_tasks("WHERE done = 0")
+    }
+
+    pub fn tasks(&self) -> Result<Vec<Task>> {
+        self.query_tasks("")
+    }
+
+    pub fn task(&self, id: i64) -> Result<Option<Task>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, title, estimate, done, created_at, uuid FROM tasks WHERE id = ?1",
+        )?;
+        let mut tasks = stmt.query_map([id], Task::from_row)?;
+        tasks.next().transpose()
+    }
+
+    pub fn task_by_uuid(&self, uuid: &str) -> Result<Option<Task>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, title, estimate, done, created_at, uuid FROM tasks WHERE uuid = ?1",
+        )?;
+        let mut tasks = stmt.query_map([uuid], Task::from_row)?;
+        tasks.next().transpose()
+    }
+
+    fn query_tasks(&self, filter: &str) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, title, estimate, done, created_at, uuid FROM tasks {filter} ORDER BY id"
+        ))?;
+        let tasks = stmt.query_map([], Task::from_row)?;
+        tasks.collect()
+    }
+
+    /// Returns `false` if there is no task with `id`.
+    pub fn set_task_done(&self, id: i64, done: bool) -> Result<bool> {
+        let n = self.conn.execute("UPDATE tasks SET done = ?1 WHERE id = ?2", (done, id))?;
+        Ok(n > 0)
+    }
+
+    /// Returns `false` if there is no task with `id`.
+    pub fn delete_task(&self, id: i64) -> Result<bool> {
+        let n = self.conn.execute("DELETE FROM tasks WHERE id = ?1", [id])?;
+        Ok(n > 0)
+    }
+}
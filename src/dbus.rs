@@ -0,0 +1,153 @@
+//! `org.pompom.Timer` on the D-Bus session bus, for desktop extensions and
+//! scripts.
+//!
+//! ```terminal
+//! $ busctl --user get-property org.pompom.Timer /org/pompom/Timer org.pompom.Timer State
+//! $ busctl --user call org.pompom.Timer /org/pompom/Timer org.pompom.Timer Pause
+//! $ dbus-monitor --session "interface='org.pompom.Timer'"
+//! ```
+
+use std::sync::Arc;
+
+use tokio::sync::broadcast::error::RecvError;
+use zbus::{dbus_interface, fdo, Connection, ConnectionBuilder, SignalContext};
+
+use crate::api::{Command, Remote, Transition};
+
+/// Bus name and interface of the service.
+pub const NAME: &str = "org.pompom.Timer";
+/// Object path of the timer.
+pub const PATH: &str = "/org/pompom/Timer";
+
+struct Timer {
+    remote: Arc<Remote>,
+}
+
+impl Timer {
+    fn send(&self, command: Command) -> fdo::Result<()> {
+        self.remote
+            .send(command)
+            .map(|_status| ())
+            .map_err(|refusal| fdo::Error::Failed(refusal.to_string()))
+    }
+}
+
+#[dbus_interface(name = "org.pompom.Timer")]
+impl Timer {
+    /// Starts a set while `pompom daemon` waits for its schedule.
+    fn start(&self) -> fdo::Result<()> {
+        self.send(Command::Start)
+    }
+
+    fn pause(&self) -> fdo::Result<()> {
+        self.send(Command::Pause)
+    }
+
+    fn resume(&self) -> fdo::Result<()> {
+        self.send(Command::Resume)
+    }
+
+    /// Ends the current phase now.
+    fn skip(&self) -> fdo::Result<()> {
+        self.send(Command::Skip)
+    }
+
+    /// Ends the set. Unlike `Ctrl+c`, `pompom daemon` keeps running and
+    /// waits for its next start.
+    fn stop(&self) -> fdo::Result<()> {
+        self.send(Command::Stop)
+    }
+
+    /// `idle`, `work`, `short_break` or `long_break`.
+    #[dbus_interface(property)]
+    fn state(&self) -> String {
+        self.remote.status().state
+    }
+
+    /// Seconds left in the phase.
+    #[dbus_interface(property)]
+    fn remaining(&self) -> u64 {
+        self.remote.status().remaining
+    }
+
+    #[dbus_interface(property)]
+    fn cycle(&self) -> u16 {
+        self.remote.status().cycle
+    }
+
+    /// Title of the task being worked on, empty if none.
+    #[dbus_interface(property)]
+    fn task(&self) -> String {
+        self.remote.status().task.unwrap_or_default()
+    }
+
+    /// Sent on each transition with its event, e.g. `work_start`, and the
+    /// state after it.
+    #[dbus_interface(signal, name = "StateChanged")]
+    async fn announce(context: &SignalContext<'_>, event: &str, state: &str) -> zbus::Result<()>;
+}
+
+/// Exports the timer at [`PATH`] under [`NAME`] on the session bus.
+pub async fn serve(remote: Arc<Remote>) -> zbus::Result<Connection> {
+    serve_on(ConnectionBuilder::session()?, remote).await
+}
+
+/// Like [`serve`], on the bus `builder` connects to. Signals are sent for as
+/// long as the timer runs, whether or not the connection is kept.
+pub async fn serve_on(
+    builder: ConnectionBuilder<'_>,
+    remote: Arc<Remote>,
+) -> zbus::Result<Connection> {
+    let mut transitions = remote.subscribe();
+    let connection =
+        builder.name(NAME)?.serve_at(PATH, Timer { remote: Arc::clone(&remote) })?.build().await?;
+    let signals = connection.clone();
+    tokio::spawn(async move {
+        let context = SignalContext::from_parts(signals.clone(), PATH.try_into()?);
+        loop {
+            let event = match transitions.recv().await {
+                Ok(Transition { event, .. }) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            if let Err(e) = announce_changes(&signals, &context, &event, &remote).await {
+                log::warn!("Failed to signal {event} on D-Bus: {e}");
+            }
+        }
+        Ok::<_, zbus::Error>(())
+    });
+    Ok(connection)
+}
+
+async fn announce_changes(
+    connection: &Connection,
+    context: &SignalContext<'_>,
+    event: &str,
+    remote: &Remote,
+) -> zbus::Result<()> {
+    Timer::announce(context, event, &remote.status().state).await?;
+    let timer = connection.object_server().interface::<_, Timer>(PATH).await?;
+    let timer = timer.get().await;
+    timer.state_changed(context).await?;
+    timer.remaining_changed(context).await?;
+    timer.cycle_changed(context).await?;
+    timer.task_changed(context).await
+}
+
+/// Whether `name` has an owner on the bus of `connection`, or with
+/// `activatable` whether the bus can start one.
+pub async fn has_name(
+    connection: &Connection,
+    name: &str,
+    activatable: bool,
+) -> zbus::Result<bool> {
+    let bus = fdo::DBusProxy::new(connection).await?;
+    if bus.name_has_owner(name.try_into()?).await? {
+        return Ok(true);
+    }
+    if !activatable {
+        return Ok(false);
+    }
+    let names = bus.list_activatable_names().await?;
+    Ok(names.iter().any(|owned| owned.as_str() == name))
+}
@@ -0,0 +1,210 @@
+//! `pompom doctor`: checks what notifications, speech, the history and the
+//! daemon depend on, and says how to fix what's missing.
+
+use std::{fmt, net::TcpStream, time::Duration};
+
+use chrono::Utc;
+use miette::Diagnostic;
+use tabled::{Table, Tabled};
+
+use crate::{
+    config::Config,
+    db::{self, Database, Session, SessionKind},
+    error::{NotificationError, PomodoroError},
+};
+
+/// Well-known name of the desktop notification service.
+const NOTIFICATIONS: &str = "org.freedesktop.Notifications";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        })
+    }
+}
+
+#[derive(Tabled)]
+struct Check {
+    check: &'static str,
+    status: Status,
+    /// Code of the error the problem is reported as.
+    category: String,
+    #[tabled(rename = "details")]
+    detail: String,
+    fix: String,
+}
+
+impl Check {
+    fn pass(check: &'static str, detail: impl Into<String>) -> Self {
+        let (category, fix) = (String::new(), String::new());
+        Self { check, status: Status::Pass, category, detail: detail.into(), fix }
+    }
+
+    fn problem(check: &'static str, status: Status, problem: impl Diagnostic) -> Self {
+        let category = problem.code().map(|code| code.to_string()).unwrap_or_default();
+        let fix = problem.help().map(|help| help.to_string()).unwrap_or_default();
+        let mut detail = problem.to_string();
+        let mut source = problem.source();
+        while let Some(cause) = source {
+            detail = format!("{detail}: {cause}");
+            source = cause.source();
+        }
+        Self { check, status, category, detail, fix }
+    }
+
+    fn warn(check: &'static str, problem: impl Diagnostic) -> Self {
+        Self::problem(check, Status::Warn, problem)
+    }
+
+    fn fail(check: &'static str, problem: impl Diagnostic) -> Self {
+        Self::problem(check, Status::Fail, problem)
+    }
+}
+
+/// `$ pompom doctor`
+pub async fn run_command() -> miette::Result<()> {
+    let (config, config_check) = check_config();
+    let mut checks = vec![config_check];
+    checks.extend(check_session_bus().await);
+    checks.push(check_speech());
+    checks.push(check_history());
+    checks.push(check_daemon(&config).await);
+    checks.push(check_colors());
+    checks.push(check_timezone());
+    println!("{}", Table::new(&checks));
+
+    let failed = checks.iter().filter(|check| check.status == Status::Fail).count();
+    if failed > 0 {
+        return Err(PomodoroError::ChecksFailed(failed).into());
+    }
+    Ok(())
+}
+
+/// The config, or the defaults if it can't be used, so the other checks can
+/// still run.
+fn check_config() -> (Config, Check) {
+    let path = Config::path();
+    match Config::load() {
+        Ok(config) if path.exists() => (config, Check::pass("config", path.display().to_string())),
+        Ok(config) => (config, Check::pass("config", "No config file, using the defaults")),
+        Err(e) => {
+            let reason = e.chain().map(ToString::to_string).collect::<Vec<_>>().join(": ");
+            (Config::default(), Check::fail("config", PomodoroError::InvalidConfig { reason }))
+        }
+    }
+}
+
+/// The session bus and a notification daemon on it, which desktop
+/// notifications go through.
+#[cfg(all(unix, not(target_os = "macos")))]
+async fn check_session_bus() -> Vec<Check> {
+    let connection = match zbus::Connection::session().await {
+        Ok(connection) => connection,
+        Err(e) => {
+            let problem = NotificationError::NoSessionBus { reason: e.to_string() };
+            return vec![Check::fail("session bus", problem)];
+        }
+    };
+    let bus = Check::pass("session bus", "Connected");
+    let notifications = match crate::dbus::has_name(&connection, NOTIFICATIONS, true).await {
+        Ok(true) => Check::pass("notifications", NOTIFICATIONS),
+        Ok(false) => Check::fail("notifications", NotificationError::NoNotificationDaemon),
+        Err(e) => {
+            let problem = NotificationError::NoSessionBus { reason: e.to_string() };
+            Check::fail("notifications", problem)
+        }
+    };
+    vec![bus, notifications]
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+async fn check_session_bus() -> Vec<Check> {
+    vec![Check::pass("notifications", "Shown by the system")]
+}
+
+/// A speech synthesizer for spoken reminders.
+fn check_speech() -> Check {
+    match ["spd-say", "espeak"].into_iter().find(|program| on_path(program)) {
+        Some(program) => Check::pass("speech", program),
+        None => Check::warn("speech", NotificationError::NoSpeech),
+    }
+}
+
+fn on_path(program: &str) -> bool {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&paths).any(|dir| dir.join(program).is_file())
+}
+
+/// That the history database opens, takes writes and has a schema this
+/// pompom knows.
+fn check_history() -> Check {
+    let path = db::default_path();
+    let history = |source| PomodoroError::History { path: path.clone(), source };
+    let db = match Database::open_history() {
+        Ok(db) => db,
+        Err(e) => return Check::fail("history", e),
+    };
+    let now = Utc::now();
+    let written =
+        db.rolled_back(|db| db.insert_session(&Session::new(SessionKind::Work, now, now)));
+    if let Err(e) = written {
+        return Check::fail("history", history(e));
+    }
+    match db.schema_version() {
+        Ok(found) if found > db::SCHEMA_VERSION => {
+            let problem = PomodoroError::NewerSchema { found, known: db::SCHEMA_VERSION };
+            Check::fail("history", problem)
+        }
+        Ok(found) => Check::pass("history", format!("{} (schema v{found})", path.display())),
+        Err(e) => Check::fail("history", history(e)),
+    }
+}
+
+/// A running `pompom daemon`, found on the session bus or through the HTTP
+/// API.
+async fn check_daemon(config: &Config) -> Check {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if let Ok(connection) = zbus::Connection::session().await {
+        if let Ok(true) = crate::dbus::has_name(&connection, crate::dbus::NAME, false).await {
+            return Check::pass("daemon", format!("{} on the session bus", crate::dbus::NAME));
+        }
+    }
+    if let Some(api) = &config.api {
+        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], api.port));
+        if TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_ok() {
+            return Check::pass("daemon", format!("HTTP API on http://{addr}"));
+        }
+    }
+    Check::warn("daemon", PomodoroError::NoTimerRunning)
+}
+
+fn check_colors() -> Check {
+    if dialoguer::console::colors_enabled() {
+        Check::pass("colors", std::env::var("TERM").unwrap_or_default())
+    } else {
+        Check::warn("colors", PomodoroError::NoColors)
+    }
+}
+
+/// The local time zone, which schedules and working hours are read in.
+fn check_timezone() -> Check {
+    let found = iana_time_zone::get_timezone().map_err(|e| e.to_string()).and_then(|name| {
+        name.parse::<chrono_tz::Tz>().map_err(|_e| format!("unknown time zone `{name}`"))
+    });
+    match found {
+        Ok(tz) => Check::pass("timezone", tz.name()),
+        Err(reason) => Check::warn("timezone", PomodoroError::UnknownTimezone { reason }),
+    }
+}
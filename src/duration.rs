@@ -0,0 +1,153 @@
+//! Human friendly durations for timer lengths, e.g. `1h30m`, `90s` or a bare `25`.
+
+use std::{fmt, str::FromStr, time};
+
+use crate::error::ParseError;
+
+/// A span of time parsed from a human friendly string.
+///
+/// Units `h`, `m` and `s` can be combined (`1h30m`, `25m30s`). A bare number
+/// is read as minutes, so `25` is the same as `25m`.
+///
+/// ```
+/// use pompom::HumanDuration;
+///
+/// let d: HumanDuration = "1h30m".parse().unwrap();
+/// assert_eq!(d.as_secs(), 5400);
+/// assert_eq!(d.to_string(), "1h30m");
+/// assert_eq!("90s".parse::<HumanDuration>().unwrap().to_string(), "1m30s");
+/// assert_eq!("25".parse::<HumanDuration>().unwrap().as_secs(), 1500);
+/// assert!("1x".parse::<HumanDuration>().is_err());
+/// assert!("".parse::<HumanDuration>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct HumanDuration(time::Duration);
+
+impl HumanDuration {
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(time::Duration::from_secs(secs))
+    }
+
+    pub const fn from_mins(mins: u64) -> Self {
+        Self::from_secs(mins * 60)
+    }
+
+    pub fn as_secs(&self) -> u64 {
+        self.0.as_secs()
+    }
+
+    pub fn as_std(&self) -> time::Duration {
+        self.0
+    }
+
+    /// As a [`chrono::Duration`], for timestamp arithmetic.
+    pub fn to_chrono(&self) -> chrono::Duration {
+        chrono::Duration::from_std(self.0).unwrap_or_else(|_e| chrono::Duration::max_value())
+    }
+}
+
+impl From<time::Duration> for HumanDuration {
+    fn from(d: time::Duration) -> Self {
+        Self(d)
+    }
+}
+
+impl FromStr for HumanDuration {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |reason: &str| ParseError { message: Some(format!("duration `{s}`: {reason}")) };
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(err("is empty"));
+        }
+        if s.chars().all(|c| c.is_ascii_digit()) {
+            let mins: u64 = s.parse().map_err(|_e| err("number is too large"))?;
+            return mins.checked_mul(60).map(Self::from_secs).ok_or_else(|| err("is too long"));
+        }
+
+        let mut secs: u64 = 0;
+        let mut digits = String::new();
+        for c in s.chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                'h' | 'm' | 's' => {
+                    if digits.is_empty() {
+                        return Err(err("expected a number before each unit"));
+                    }
+                    let n: u64 = digits.parse().map_err(|_e| err("number is too large"))?;
+                    let unit = match c {
+                        'h' => 3600,
+                        'm' => 60,
+                        _ => 1,
+                    };
+                    secs = n
+                        .checked_mul(unit)
+                        .and_then(|n| secs.checked_add(n))
+                        .ok_or_else(|| err("is too long"))?;
+                    digits.clear();
+                }
+                c if c.is_whitespace() => {}
+                _ => return Err(err("expected units `h`, `m` or `s`")),
+            }
+        }
+        if !digits.is_empty() {
+            return Err(err("missing unit after the last number"));
+        }
+
+        Ok(Self::from_secs(secs))
+    }
+}
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.as_secs();
+        let (h, m, s) = (secs / 3600, secs % 3600 / 60, secs % 60);
+        if h > 0 {
+            write!(f, "{h}h")?;
+        }
+        if m > 0 {
+            write!(f, "{m}m")?;
+        }
+        if s > 0 || secs == 0 {
+            write!(f, "{s}s")?;
+        }
+        Ok(())
+    }
+}
+
+/// Written as a string such as `"25m"` in the config file. A bare integer is
+/// read as minutes, like on the command line.
+impl serde::Serialize for HumanDuration {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for HumanDuration {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = HumanDuration;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a duration such as \"1h30m\", \"90s\" or a number of minutes")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                v.checked_mul(60).map(HumanDuration::from_secs).ok_or_else(|| E::custom("too long"))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                u64::try_from(v).map_err(E::custom).and_then(|v| self.visit_u64(v))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
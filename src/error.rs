@@ -1,110 +1,210 @@
-//
-// Code copied and slightly modified from [24seconds/rust-cli-pomodoro](https://github.com/24seconds/rust-cli-pomodoro).
-//
-
-use std::{
-    error::Error,
-    fmt,
-    io,
-    result,
-};
-
-use bincode::error::{
-    DecodeError,
-    EncodeError,
-};
+//! What can go wrong, as [`miette`] diagnostics with a code, help where
+//! there's something to do about it, and the error that caused it.
+//!
+//! Commands return [`miette::Result`], so each of these converts with `?`.
+//! Failures that don't need to stop the timer, like a notification that
+//! can't be shown, are reported as warnings instead.
+
+use std::{io, path::PathBuf};
+
+use miette::Diagnostic;
 use notify_rust::error::Error as NotifyRustError;
-use reqwest::Error as ReqwestError;
-use serde_json::error::Error as SerdeJsonError;
+use thiserror::Error;
 
-pub type NotifyResult = result::Result<(), NotificationError>;
+pub type NotifyResult = Result<(), NotificationError>;
 
+#[derive(Debug, Error, Diagnostic)]
 pub enum PomodoroError {
-    NotificationError,
-    ConfigurationError,
-    UdsHandlerError,
-    UserInputHandlerError,
-    ParseError,
-}
+    #[error("No profile named `{name}`")]
+    #[diagnostic(code(pompom::config::profile))]
+    UnknownProfile {
+        name: String,
+        /// The profiles there are, or how to add one.
+        #[help]
+        known: String,
+    },
 
-/// NotificationError enumerates all errors related to notification.
-#[derive(Debug)]
-pub enum NotificationError {
-    Desktop(NotifyRustError),
-    EmptyConfiguration,
-    NewNotification(ParseError),
-    DeletionFail(String),
-}
+    #[error("Outside working hours ({hours})")]
+    #[diagnostic(
+        code(pompom::config::working_hours),
+        help("Set `strict = false` under [working_hours] to only warn")
+    )]
+    OutsideWorkingHours { hours: String },
 
-// impl fmt::Debug for NotificationError {
-//     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-//         match self {
-//             Self::Desktop(arg0) => f.debug_tuple("Desktop").field(arg0).finish(),
-//             Self::EmptyConfiguration => write!(f, "EmptyConfiguration"),
-//             Self::NewNotification(arg0) => f.debug_tuple("NewNotification").field(arg0).finish(),
-//             Self::DeletionFail(arg0) => f.debug_tuple("DeletionFail").field(arg0).finish(),
-//         }
-//     }
-// }
-
-impl From<NotifyRustError> for NotificationError {
-    fn from(v: NotifyRustError) -> Self {
-        Self::Desktop(v)
-    }
-}
+    #[error("No schedules configured")]
+    #[diagnostic(
+        code(pompom::schedule::none),
+        help("Add one with `pompom schedule add --at 09:30`")
+    )]
+    NoSchedules,
 
-/// # impl Display for DataStoreError {
-/// #     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-/// #         unimplemented!()
-/// #     }
-/// # }
-impl fmt::Display for NotificationError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            NotificationError::Desktop(_) => write!(f, "NotificationError::Desktop"),
-            NotificationError::EmptyConfiguration => write!(f, "configuration is empty"),
-            NotificationError::NewNotification(err) => {
-                write!(f, "failed to get new notification: {}", err)
-            }
-            NotificationError::DeletionFail(msg) => write!(f, "{}", msg),
-        }
-    }
-}
+    #[error("No schedule #{0}")]
+    #[diagnostic(
+        code(pompom::schedule::missing),
+        help("See `pompom schedule list` for schedule numbers")
+    )]
+    NoSchedule(usize),
 
-/// # impl std::error::Error for DataStoreError {}
-impl std::error::Error for NotificationError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        match self {
-            NotificationError::Desktop(ref e) => Some(e),
-            NotificationError::EmptyConfiguration => None,
-            NotificationError::NewNotification(ref e) => Some(e),
-            NotificationError::DeletionFail(_) => None,
-        }
-    }
-}
+    #[error("No task #{0}")]
+    #[diagnostic(code(pompom::task::missing), help("See `pompom task list` for task numbers"))]
+    NoTask(i64),
 
-#[derive(Debug)]
-pub struct ParseError {
-    pub message: Option<String>,
-}
+    #[error("No `{0}` task source")]
+    #[diagnostic(
+        code(pompom::source::missing),
+        help("Set its path under [sources] in the config")
+    )]
+    UnknownSource(String),
+
+    #[error("No open task @{source_name}:{id} in {}", path.display())]
+    #[diagnostic(code(pompom::source::item), help("See `pompom task list`"))]
+    NoSourceItem { source_name: String, id: usize, path: PathBuf },
+
+    #[error("Can't tell the format of {}", path.display())]
+    #[diagnostic(
+        code(pompom::import::format),
+        help("Pass --format csv, json, ndjson or taskwarrior")
+    )]
+    UnknownFormat { path: PathBuf },
+
+    #[error("The HTTP API has no token")]
+    #[diagnostic(
+        code(pompom::api::token),
+        help("Set a `token` in the [api] table of the config to enable it")
+    )]
+    NoApiToken,
+
+    #[error("Failed to read the answer")]
+    #[diagnostic(
+        code(pompom::prompt),
+        help("Prompts need a terminal, set the times with -w, -s and -l instead")
+    )]
+    Prompt(#[source] io::Error),
+
+    #[error("{reason}")]
+    #[diagnostic(
+        code(pompom::config::invalid),
+        help("Fix the file, or move it aside to start from the defaults")
+    )]
+    InvalidConfig { reason: String },
 
-impl fmt::Display for ParseError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &self.message {
-            Some(msg) => write!(f, "error occurred while parsing: {}", msg),
-            None => write!(f, "error occurred while parsing"),
-        }
-    }
+    #[error("Failed to use the history database at {}", path.display())]
+    #[diagnostic(
+        code(pompom::history::open),
+        help("Make sure its directory is writable, or point XDG_DATA_HOME at one that is")
+    )]
+    History {
+        path: PathBuf,
+        #[source]
+        source: rusqlite::Error,
+    },
+
+    #[error("The history database is at schema v{found}, this pompom knows up to v{known}")]
+    #[diagnostic(
+        code(pompom::history::schema),
+        help("It was written by a newer pompom, upgrade this one")
+    )]
+    NewerSchema { found: i32, known: i32 },
+
+    #[error("No timer is running")]
+    #[diagnostic(
+        code(pompom::daemon),
+        help("Start one with `pompom daemon`, it's found over D-Bus or the HTTP API")
+    )]
+    NoTimerRunning,
+
+    #[error("Named timers are reached through the HTTP API, which isn't set up")]
+    #[diagnostic(
+        code(pompom::timers::api),
+        help("Add an [api] table with a `token` to the config, then run `pompom daemon`")
+    )]
+    NoApi,
+
+    #[error("No timer named `{0}`")]
+    #[diagnostic(code(pompom::timers::missing), help("See `pompom list` for the running timers"))]
+    NoNamedTimer(String),
+
+    #[error("`{0}` can't name a timer")]
+    #[diagnostic(
+        code(pompom::timers::name),
+        help("Timer names are letters, digits, `-` and `_`, e.g. `standup`")
+    )]
+    TimerName(String),
+
+    #[error("Colors are off")]
+    #[diagnostic(
+        code(pompom::terminal::color),
+        help("Output isn't a terminal, or TERM, NO_COLOR or CLICOLOR turn colors off")
+    )]
+    NoColors,
+
+    #[error("Can't tell the local time zone ({reason}), using UTC")]
+    #[diagnostic(code(pompom::timezone), help("Set TZ to an IANA name, e.g. `TZ=Europe/Berlin`"))]
+    UnknownTimezone { reason: String },
+
+    #[error("{0} of the checks failed")]
+    #[diagnostic(code(pompom::doctor), help("See the fixes in the report above"))]
+    ChecksFailed(usize),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Notification(#[from] NotificationError),
 }
 
-impl std::error::Error for ParseError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
+/// A notification that couldn't be given, which never stops the timer.
+#[derive(Debug, Error, Diagnostic)]
+pub enum NotificationError {
+    #[error("Failed to show a desktop notification")]
+    #[diagnostic(
+        code(pompom::notify::desktop),
+        help(
+            "Desktop notifications go over D-Bus, install libdbus-1 (e.g. `apt install \
+             libdbus-1-3`) and make sure a notification daemon is running"
+        )
+    )]
+    Desktop(#[from] NotifyRustError),
+
+    #[error("Failed to speak `{text}`")]
+    #[diagnostic(
+        code(pompom::notify::speech),
+        help("Spoken reminders use `spd-say`, install speech-dispatcher (e.g. `apt install speech-dispatcher`)")
+    )]
+    Speech {
+        text: String,
+        #[source]
+        source: xshell::Error,
+    },
+
+    #[error("No D-Bus session bus ({reason})")]
+    #[diagnostic(
+        code(pompom::notify::bus),
+        help(
+            "Desktop notifications go over D-Bus, install libdbus-1 (e.g. `apt install \
+             libdbus-1-3`) and run pompom in a desktop session"
+        )
+    )]
+    NoSessionBus { reason: String },
+
+    #[error("No notification daemon on the session bus")]
+    #[diagnostic(
+        code(pompom::notify::daemon),
+        help("Install and start one, e.g. dunst, mako or the one of your desktop")
+    )]
+    NoNotificationDaemon,
+
+    #[error("Neither `spd-say` nor `espeak` is installed")]
+    #[diagnostic(
+        code(pompom::notify::speech),
+        help("Spoken reminders use `spd-say`, install speech-dispatcher (e.g. `apt install speech-dispatcher`)")
+    )]
+    NoSpeech,
 }
 
-impl ParseError {
-    fn new(message: String) -> Self {
-        Self { message: Some(message) }
-    }
+/// Text that isn't what was expected, e.g. a duration or a line of an
+/// export.
+#[derive(Debug, Error, Diagnostic)]
+#[error("error occurred while parsing{}", message.as_ref().map(|m| format!(": {m}")).unwrap_or_default())]
+#[diagnostic(code(pompom::parse))]
+pub struct ParseError {
+    pub message: Option<String>,
 }
@@ -0,0 +1,236 @@
+//! What happens while a pomodoro set runs, published on a
+//! [`tokio::sync::broadcast`] channel.
+//!
+//! The progress bar, hooks, webhooks, the HTTP API, desktop notifications
+//! and the history database all follow these events, and so can code that
+//! embeds the library:
+//!
+//! ```no_run
+//! use pompom::{events::Event, App, PomoFocusCli};
+//!
+//! # async fn example() -> miette::Result<()> {
+//! let mut app = App::new(PomoFocusCli::default());
+//! let mut events = app.subscribe();
+//! tokio::spawn(async move {
+//!     while let Ok(event) = events.recv().await {
+//!         if let Event::PhaseEnded { phase, .. } = event {
+//!             println!("{} is over", phase.kind);
+//!         }
+//!     }
+//! });
+//! app.run().await
+//! # }
+//! ```
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{
+    self,
+    error::{RecvError, TryRecvError},
+};
+
+use crate::HumanDuration;
+
+/// Events a slow subscriber can fall behind by before it misses some.
+const CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseKind {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl PhaseKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PhaseKind::Work => "work",
+            PhaseKind::ShortBreak => "short_break",
+            PhaseKind::LongBreak => "long_break",
+        }
+    }
+}
+
+impl fmt::Display for PhaseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A phase of a set, as it was started.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Phase {
+    pub kind: PhaseKind,
+    /// Title of the task a work phase is spent on.
+    pub task: Option<String>,
+    /// The work cycle this phase is, or follows for a break.
+    pub cycle: u16,
+    /// Work cycles in the set.
+    pub cycles: u16,
+    pub length: HumanDuration,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    PhaseStarted {
+        phase: Phase,
+    },
+    /// Sent each second the phase counts down.
+    Tick {
+        kind: PhaseKind,
+        remaining: HumanDuration,
+    },
+    /// Sent every five minutes into a phase, for a nudge that it's still
+    /// running.
+    ReminderDue {
+        phase: Phase,
+        elapsed: HumanDuration,
+    },
+    Paused {
+        phase: Phase,
+        remaining: HumanDuration,
+    },
+    Resumed {
+        phase: Phase,
+        remaining: HumanDuration,
+    },
+    /// The phase ran out, or was skipped.
+    PhaseEnded {
+        phase: Phase,
+        ended_at: DateTime<Utc>,
+        /// Time counted down, without pauses.
+        elapsed: HumanDuration,
+        /// Times it was paused.
+        pauses: u32,
+        skipped: bool,
+    },
+    /// The reflection written after the work phase that just ended.
+    Reflected {
+        phase: Phase,
+        note: String,
+    },
+    /// The phase was stopped, which ends the set.
+    Interrupted {
+        phase: Phase,
+        ended_at: DateTime<Utc>,
+        elapsed: HumanDuration,
+        remaining: HumanDuration,
+        pauses: u32,
+        /// Stopped with `Ctrl+c` rather than through the HTTP API or D-Bus.
+        cancelled: bool,
+    },
+    /// The long break that ends the set is over.
+    SetCompleted {
+        cycles: u16,
+        at: DateTime<Utc>,
+    },
+}
+
+impl Event {
+    /// Whether nothing follows this event until the next set starts.
+    pub fn ends_set(&self) -> bool {
+        matches!(self, Event::Interrupted { .. } | Event::SetCompleted { .. })
+    }
+}
+
+/// The sending end of the events, owned by the [`App`](crate::App).
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+    /// Events published so far.
+    published: AtomicU64,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(CAPACITY).0, published: AtomicU64::new(0) }
+    }
+}
+
+impl EventBus {
+    /// Sends `event` to every subscriber, returning how many events have
+    /// been published with it.
+    pub fn publish(&self, event: Event) -> u64 {
+        // No one listening isn't an error.
+        let _ = self.sender.send(event);
+        self.published.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub fn published(&self) -> u64 {
+        self.published.load(Ordering::SeqCst)
+    }
+
+    /// Every event from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// The events from now on until the current or next set ends.
+    pub fn subscribe_set(&self) -> SetEvents {
+        SetEvents { receiver: self.subscribe(), seen: self.published(), over: false }
+    }
+}
+
+/// The events of one set, see [`EventBus::subscribe_set`].
+#[derive(Debug)]
+pub struct SetEvents {
+    receiver: broadcast::Receiver<Event>,
+    seen: u64,
+    over: bool,
+}
+
+impl SetEvents {
+    /// The next event, `None` after the one that ends the set.
+    pub async fn next(&mut self) -> Option<Event> {
+        while !self.over {
+            match self.receiver.recv().await {
+                Ok(event) => {
+                    self.seen += 1;
+                    self.over = event.ends_set();
+                    return Some(event);
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    self.seen += missed;
+                    log::warn!("Fell behind and missed {missed} events");
+                }
+                Err(RecvError::Closed) => self.over = true,
+            }
+        }
+        None
+    }
+
+    /// The next event if one is waiting, like [`SetEvents::next`] without
+    /// waiting for it.
+    pub fn try_next(&mut self) -> Option<Event> {
+        while !self.over {
+            match self.receiver.try_recv() {
+                Ok(event) => {
+                    self.seen += 1;
+                    self.over = event.ends_set();
+                    return Some(event);
+                }
+                Err(TryRecvError::Lagged(missed)) => {
+                    self.seen += missed;
+                    log::warn!("Fell behind and missed {missed} events");
+                }
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Closed) => self.over = true,
+            }
+        }
+        None
+    }
+
+    /// Events received or missed so far, counted like
+    /// [`EventBus::published`].
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+}
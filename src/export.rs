@@ -0,0 +1,533 @@
+//! `pompom export`: session history in formats other tools understand.
+
+use std::{path::PathBuf, str::FromStr};
+
+use chrono::{DateTime, Duration, NaiveDate, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use clap::ValueEnum;
+use miette::{IntoDiagnostic, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{Database, Session, SessionKind, SessionOutcome, Task},
+    error::ParseError,
+    schedule, warrior,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// iCalendar, one event per completed work phase.
+    Ics,
+    /// One row per session or task, with a `type` column telling them apart.
+    Csv,
+    /// A single object with `sessions` and `tasks` arrays.
+    Json,
+    /// One JSON object per line, tagged with `type`.
+    Ndjson,
+    /// Interval lines added to Timewarrior's data files.
+    Timewarrior,
+}
+
+/// Everything `pompom export` writes and `pompom import` reads back.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Archive {
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+    #[serde(default)]
+    pub tasks: Vec<Task>,
+}
+
+/// A line of NDJSON or a row of CSV.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    Session(Session),
+    Task(Task),
+}
+
+/// Columns of the CSV format, shared by session and task rows.
+const CSV_COLUMNS: [&str; 14] = [
+    "type",
+    "uuid",
+    "kind",
+    "label",
+    "task",
+    "note",
+    "interruptions",
+    "outcome",
+    "started_at",
+    "ended_at",
+    "title",
+    "estimate",
+    "done",
+    "created_at",
+];
+
+impl Archive {
+    fn records(&self) -> impl Iterator<Item = Record> + '_ {
+        self.sessions
+            .iter()
+            .cloned()
+            .map(Record::Session)
+            .chain(self.tasks.iter().cloned().map(Record::Task))
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self).map(|json| format!("{json}\n"))
+    }
+
+    pub fn from_json(s: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(s)
+    }
+
+    pub fn to_ndjson(&self) -> serde_json::Result<String> {
+        self.records()
+            .map(|record| serde_json::to_string(&record).map(|line| format!("{line}\n")))
+            .collect()
+    }
+
+    pub fn from_ndjson(s: &str) -> Result<Self, ParseError> {
+        let mut archive = Self::default();
+        for (n, line) in s.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let record = serde_json::from_str(line)
+                .map_err(|e| ParseError { message: Some(format!("line {}: {e}", n + 1)) })?;
+            archive.push(record);
+        }
+        Ok(archive)
+    }
+
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use pompom::db::{Session, SessionKind, SessionOutcome, Task};
+    /// use pompom::export::Archive;
+    ///
+    /// let start = Utc.with_ymd_and_hms(2026, 10, 19, 7, 0, 0).unwrap();
+    /// let archive = Archive {
+    ///     sessions: vec![Session::new(SessionKind::Work, start, start)
+    ///         .with_task(Some("Write report".into()))
+    ///         .with_note(Some("Said \"done\",\nthen wasn't".into()))
+    ///         .with_outcome(SessionOutcome::Skipped)],
+    ///     tasks: vec![Task::new("Write report").with_estimate(2)],
+    /// };
+    /// let csv = archive.to_csv();
+    /// assert!(csv.starts_with("type,uuid,kind,"));
+    /// assert_eq!(Archive::from_csv(&csv).unwrap(), archive);
+    /// ```
+    pub fn to_csv(&self) -> String {
+        let mut out = format!("{}\n", CSV_COLUMNS.join(","));
+        for record in self.records() {
+            let time = |t: DateTime<Utc>| t.to_rfc3339();
+            let row: [String; 14] = match record {
+                Record::Session(s) => [
+                    "session".into(),
+                    s.uuid,
+                    s.kind.to_string(),
+                    s.label.unwrap_or_default(),
+                    s.task.unwrap_or_default(),
+                    s.note.unwrap_or_default(),
+                    s.interruptions.to_string(),
+                    s.outcome.to_string(),
+                    time(s.started_at),
+                    time(s.ended_at),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                ],
+                Record::Task(t) => [
+                    "task".into(),
+                    t.uuid,
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    t.title,
+                    t.estimate.to_string(),
+                    t.done.to_string(),
+                    time(t.created_at),
+                ],
+            };
+            let row: Vec<String> = row.iter().map(|field| csv_quote(field)).collect();
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn from_csv(s: &str) -> Result<Self, ParseError> {
+        let mut rows = csv_rows(s).into_iter();
+        let header = rows.next().unwrap_or_default();
+        let mut archive = Self::default();
+        for (n, row) in rows.enumerate() {
+            let err = |e: String| ParseError { message: Some(format!("row {}: {e}", n + 1)) };
+            let field = |name: &str| {
+                header
+                    .iter()
+                    .position(|column| column == name)
+                    .and_then(|i| row.get(i))
+                    .map_or("", String::as_str)
+            };
+            let optional = |name: &str| Some(field(name).to_string()).filter(|s| !s.is_empty());
+            let time = |name: &str| {
+                DateTime::parse_from_rfc3339(field(name))
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|e| err(format!("{name} `{}`: {e}", field(name))))
+            };
+            let number = |name: &str| {
+                let value = field(name);
+                if value.is_empty() {
+                    return Ok(0);
+                }
+                value.parse::<u32>().map_err(|e| err(format!("{name} `{value}`: {e}")))
+            };
+            let record = match field("type") {
+                "session" => Record::Session(Session {
+                    id: None,
+                    uuid: field("uuid").to_string(),
+                    kind: field("kind").parse().map_err(err)?,
+                    label: optional("label"),
+                    started_at: time("started_at")?,
+                    ended_at: time("ended_at")?,
+                    task: optional("task"),
+                    note: optional("note"),
+                    interruptions: number("interruptions")?,
+                    outcome: match field("outcome") {
+                        "" => SessionOutcome::default(),
+                        outcome => outcome.parse().map_err(err)?,
+                    },
+                }),
+                "task" => Record::Task(Task {
+                    id: None,
+                    uuid: field("uuid").to_string(),
+                    title: field("title").to_string(),
+                    estimate: u16::try_from(number("estimate")?)
+                        .map_err(|e| err(format!("estimate: {e}")))?,
+                    done: field("done") == "true",
+                    created_at: time("created_at")?,
+                }),
+                other => return Err(err(format!("unknown record type `{other}`"))),
+            };
+            if field("uuid").is_empty() {
+                return Err(err(String::from("missing uuid")));
+            }
+            archive.push(record);
+        }
+        Ok(archive)
+    }
+
+    fn push(&mut self, record: Record) {
+        match record {
+            Record::Session(session) => self.sessions.push(session),
+            Record::Task(task) => self.tasks.push(task),
+        }
+    }
+}
+
+fn csv_quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Splits CSV into rows of fields, honouring quoted fields with embedded
+/// commas, quotes and newlines.
+fn csv_rows(s: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Inclusive range of dates written as `2026-10-01..2026-10-31`. Either end
+/// may be left out.
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use pompom::export::DateRange;
+///
+/// let range: DateRange = "2026-10-01..2026-10-31".parse().unwrap();
+/// assert!(range.contains(NaiveDate::from_ymd_opt(2026, 10, 31).unwrap()));
+/// assert!(!range.contains(NaiveDate::from_ymd_opt(2026, 11, 1).unwrap()));
+/// assert!("..2026-10-31".parse::<DateRange>().unwrap().from.is_none());
+/// assert!("2026-10-01".parse::<DateRange>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl DateRange {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|from| from <= date) && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+impl FromStr for DateRange {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |e: String| ParseError { message: Some(e) };
+        let (from, to) = s.split_once("..").ok_or_else(|| {
+            err(format!("range `{s}`, expected FROM..TO such as 2026-10-01..2026-10-31"))
+        })?;
+        let date = |d: &str| {
+            let d = d.trim();
+            if d.is_empty() {
+                return Ok(None);
+            }
+            NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                .map(Some)
+                .map_err(|e| err(format!("date `{d}`: {e}, expected YYYY-MM-DD")))
+        };
+        Ok(Self { from: date(from)?, to: date(to)? })
+    }
+}
+
+/// `$ pompom export --format ics --range 2026-10-01..2026-10-31`
+///
+/// The range only applies to sessions, tasks are always exported in full.
+/// With `dry_run`, what would be written is printed instead.
+pub fn run_command(
+    format: ExportFormat,
+    range: Option<&DateRange>,
+    timezone: Option<Tz>,
+    output: Option<&PathBuf>,
+    dry_run: bool,
+) -> miette::Result<()> {
+    let tz = timezone.unwrap_or_else(schedule::local_timezone);
+    let db = Database::open_history()?;
+    let sessions: Vec<Session> = db
+        .sessions()
+        .into_diagnostic()
+        .wrap_err("Failed to read session history")?
+        .into_iter()
+        .filter(|s| range.is_none_or(|r| r.contains(s.started_at.with_timezone(&tz).date_naive())))
+        .collect();
+
+    let archive = || -> miette::Result<Archive> {
+        let tasks = db.tasks().into_diagnostic().wrap_err("Failed to read tasks")?;
+        Ok(Archive { sessions: sessions.clone(), tasks })
+    };
+    let out = match format {
+        ExportFormat::Ics => to_ics(&sessions, timezone),
+        ExportFormat::Csv => archive()?.to_csv(),
+        ExportFormat::Json => archive()?.to_json().into_diagnostic()?,
+        ExportFormat::Ndjson => archive()?.to_ndjson().into_diagnostic()?,
+        ExportFormat::Timewarrior if output.is_none() => {
+            return export_timewarrior(&sessions, dry_run);
+        }
+        ExportFormat::Timewarrior => sessions
+            .iter()
+            .filter(|s| s.kind == SessionKind::Work)
+            .map(|s| format!("{}\n", warrior::interval_line(s)))
+            .collect(),
+    };
+    let Some(path) = output.filter(|_| !dry_run) else {
+        print!("{out}");
+        return Ok(());
+    };
+    std::fs::write(path, out)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to write {}", path.display()))
+}
+
+/// Adds work phases missing from Timewarrior's data files.
+fn export_timewarrior(sessions: &[Session], dry_run: bool) -> miette::Result<()> {
+    let dir = warrior::timewarrior_data_dir();
+    let files = warrior::pending_intervals(&dir, sessions)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to read Timewarrior data in {}", dir.display()))?;
+    if files.is_empty() {
+        println!("Timewarrior is up to date");
+        return Ok(());
+    }
+    if !dry_run {
+        warrior::append_intervals(&files)
+            .into_diagnostic()
+            .wrap_err("Failed to write Timewarrior data")?;
+    }
+    let verb = if dry_run { "Would add" } else { "Added" };
+    for (path, lines) in &files {
+        println!("{verb} {} intervals to {}", lines.len(), path.display());
+        if dry_run {
+            for line in lines {
+                println!("  {line}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes completed work phases as `VEVENT`s. Times are in UTC unless a
+/// `timezone` is given, in which case they carry its `TZID` and the calendar
+/// includes a matching `VTIMEZONE`.
+///
+/// ```
+/// use chrono::{Duration, TimeZone, Utc};
+/// use pompom::db::{Session, SessionKind, SessionOutcome};
+/// use pompom::export::to_ics;
+///
+/// let start = Utc.with_ymd_and_hms(2026, 10, 19, 7, 0, 0).unwrap();
+/// let session = Session::new(SessionKind::Work, start, start + Duration::minutes(25))
+///     .with_task(Some("Write report".into()))
+///     .with_note(Some("Draft done, numbers missing".into()));
+/// let later = start + Duration::hours(1);
+/// let stopped = Session::new(SessionKind::Work, later, later + Duration::minutes(10))
+///     .with_task(Some("Review".into()))
+///     .with_outcome(SessionOutcome::Interrupted);
+///
+/// let ics = to_ics(&[session.clone(), stopped], None);
+/// assert!(ics.contains("DTSTART:20261019T070000Z\r\n"));
+/// assert!(ics.contains("SUMMARY:Write report\r\n"));
+/// assert!(ics.contains("DESCRIPTION:Draft done\\, numbers missing\r\n"));
+/// assert!(ics.contains(&format!("UID:{}@pompom\r\n", session.uuid)));
+/// assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+/// assert!(!ics.contains("Review"));
+///
+/// let ics = to_ics(&[session], Some(chrono_tz::Europe::Berlin));
+/// assert!(ics.contains("DTSTART;TZID=Europe/Berlin:20261019T090000\r\n"));
+/// assert!(ics.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/Berlin\r\n"));
+/// ```
+pub fn to_ics(sessions: &[Session], timezone: Option<Tz>) -> String {
+    let work: Vec<&Session> = sessions
+        .iter()
+        .filter(|s| s.kind == SessionKind::Work && s.outcome == SessionOutcome::Completed)
+        .collect();
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!("PRODID:-//pompom//pompom {}//EN", env!("CARGO_PKG_VERSION")),
+        String::from("CALSCALE:GREGORIAN"),
+    ];
+    if let (Some(tz), Some(first), Some(last)) = (timezone, work.first(), work.last()) {
+        lines.extend(vtimezone(tz, first.started_at, last.ended_at));
+    }
+    let utc = |dt: DateTime<Utc>| dt.format("%Y%m%dT%H%M%SZ").to_string();
+    let time = |name: &str, dt: DateTime<Utc>| {
+        let local = timezone.map(|tz| dt.with_timezone(&tz).naive_local());
+        match (timezone, local) {
+            // Wall-clock times repeated when clocks go back would be read as
+            // the first occurrence, so those stay in UTC.
+            (Some(tz), Some(local)) if tz.from_local_datetime(&local).single().is_some() => {
+                format!("{name};TZID={tz}:{}", local.format("%Y%m%dT%H%M%S"))
+            }
+            _ => format!("{name}:{}", utc(dt)),
+        }
+    };
+    for session in work {
+        lines.push(String::from("BEGIN:VEVENT"));
+        // The uuid follows the session through `pompom import`, so it's the
+        // same event whichever machine exports it.
+        lines.push(format!("UID:{}@pompom", session.uuid));
+        lines.push(format!("DTSTAMP:{}", utc(session.ended_at)));
+        lines.push(time("DTSTART", session.started_at));
+        lines.push(time("DTEND", session.ended_at));
+        let summary = session.task.as_deref().or(session.label.as_deref()).unwrap_or("Pomodoro");
+        lines.push(format!("SUMMARY:{}", escape(summary)));
+        if let Some(note) = &session.note {
+            lines.push(format!("DESCRIPTION:{}", escape(note)));
+        }
+        lines.push(String::from("CATEGORIES:Pomodoro"));
+        lines.push(String::from("END:VEVENT"));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+
+    lines.iter().fold(String::new(), |mut out, line| {
+        fold(&mut out, line);
+        out
+    })
+}
+
+/// A `VTIMEZONE` with one observance per UTC offset in effect between `from`
+/// and `to`, so clients without the IANA database still get the right times.
+fn vtimezone(tz: Tz, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<String> {
+    let offset_at = |t: DateTime<Utc>| tz.offset_from_utc_datetime(&t.naive_utc());
+    let mut lines = vec![String::from("BEGIN:VTIMEZONE"), format!("TZID:{tz}")];
+    let mut observance = |at: DateTime<Utc>, before: i32| {
+        let offset = offset_at(at);
+        let kind = if offset.dst_offset().is_zero() { "STANDARD" } else { "DAYLIGHT" };
+        let onset = at.naive_utc() + Duration::seconds(i64::from(before));
+        lines.push(format!("BEGIN:{kind}"));
+        lines.push(format!("DTSTART:{}", onset.format("%Y%m%dT%H%M%S")));
+        lines.push(format!("TZOFFSETFROM:{}", format_offset(before)));
+        lines.push(format!("TZOFFSETTO:{}", format_offset(offset.fix().local_minus_utc())));
+        lines.push(format!("TZNAME:{}", offset.abbreviation()));
+        lines.push(format!("END:{kind}"));
+    };
+
+    let start = (from - Duration::days(1))
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .map_or(from, |midnight| Utc.from_utc_datetime(&midnight));
+    let mut current = offset_at(start).fix().local_minus_utc();
+    observance(start, current);
+    let mut t = start;
+    while t < to {
+        let next = t + Duration::hours(1);
+        if offset_at(next).fix().local_minus_utc() != current {
+            // Narrow the change down to the minute it happened.
+            let mut at = t;
+            while offset_at(at).fix().local_minus_utc() == current {
+                at += Duration::minutes(1);
+            }
+            observance(at, current);
+            current = offset_at(at).fix().local_minus_utc();
+        }
+        t = next;
+    }
+    lines.push(String::from("END:VTIMEZONE"));
+    lines
+}
+
+fn format_offset(secs: i32) -> String {
+    let sign = if secs < 0 { '-' } else { '+' };
+    let mins = secs.unsigned_abs() / 60;
+    format!("{sign}{:02}{:02}", mins / 60, mins % 60)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// Appends `line` folded at 75 octets, as RFC 5545 requires.
+fn fold(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
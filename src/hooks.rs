@@ -0,0 +1,311 @@
+//! Commands run on pomodoro state transitions.
+//!
+//! ```toml
+//! [hooks]
+//! timeout = "10s"
+//! work_start = ["dunstctl set-paused true", "playerctl pause"]
+//! break_start = ["dunstctl set-paused false"]
+//! set_end = ["git -C ~/src/app commit -am WIP"]
+//! ```
+//!
+//! Each command runs through `sh -c` with these variables set:
+//!
+//! - `POMPOM_EVENT`: the hook's event, e.g. `work_start`
+//! - `POMPOM_STATE`: `work`, `short_break` or `long_break`
+//! - `POMPOM_TASK`: title of the task being worked on, if any
+//! - `POMPOM_CYCLE` and `POMPOM_CYCLES`: the work cycle, and how many make a set
+//! - `POMPOM_REMAINING`: seconds left in the phase
+
+use std::{
+    fmt,
+    io::Read,
+    process::{Command, Stdio},
+    sync::Arc,
+    time::Instant,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use xshell::{cmd, Shell};
+
+use crate::{
+    events::{Event, Phase, PhaseKind, SetEvents},
+    HumanDuration,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    WorkStart,
+    WorkEnd,
+    BreakStart,
+    BreakEnd,
+    SetEnd,
+    Paused,
+    Interrupted,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::WorkStart => "work_start",
+            HookEvent::WorkEnd => "work_end",
+            HookEvent::BreakStart => "break_start",
+            HookEvent::BreakEnd => "break_end",
+            HookEvent::SetEnd => "set_end",
+            HookEvent::Paused => "paused",
+            HookEvent::Interrupted => "interrupted",
+        }
+    }
+
+    /// The hook `event` fires, if any, and where in the set it does.
+    pub fn of(event: &Event) -> Option<(Self, HookContext<'_>)> {
+        let work = |phase: &Phase| phase.kind == PhaseKind::Work;
+        let hook = match event {
+            Event::PhaseStarted { phase } => {
+                let hook = if work(phase) { HookEvent::WorkStart } else { HookEvent::BreakStart };
+                (hook, HookContext::new(phase, phase.length))
+            }
+            Event::PhaseEnded { phase, .. } => {
+                let hook = if work(phase) { HookEvent::WorkEnd } else { HookEvent::BreakEnd };
+                (hook, HookContext::new(phase, HumanDuration::default()))
+            }
+            Event::Paused { phase, remaining } => {
+                (HookEvent::Paused, HookContext::new(phase, *remaining))
+            }
+            Event::Interrupted { phase, remaining, .. } => {
+                (HookEvent::Interrupted, HookContext::new(phase, *remaining))
+            }
+            Event::SetCompleted { cycles, .. } => {
+                let context = HookContext {
+                    state: PhaseKind::LongBreak.as_str(),
+                    task: None,
+                    cycle: *cycles,
+                    cycles: *cycles,
+                    remaining: HumanDuration::default(),
+                };
+                (HookEvent::SetEnd, context)
+            }
+            Event::Tick { .. }
+            | Event::ReminderDue { .. }
+            | Event::Resumed { .. }
+            | Event::Reflected { .. } => return None,
+        };
+        Some(hook)
+    }
+}
+
+impl fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Commands to run per event, from the `[hooks]` table of the config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    /// Kill a command still running after this long.
+    pub timeout: HumanDuration,
+    pub work_start: Vec<String>,
+    pub work_end: Vec<String>,
+    pub break_start: Vec<String>,
+    pub break_end: Vec<String>,
+    pub set_end: Vec<String>,
+    pub paused: Vec<String>,
+    pub interrupted: Vec<String>,
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Self {
+            timeout: HumanDuration::from_secs(10),
+            work_start: Vec::new(),
+            work_end: Vec::new(),
+            break_start: Vec::new(),
+            break_end: Vec::new(),
+            set_end: Vec::new(),
+            paused: Vec::new(),
+            interrupted: Vec::new(),
+        }
+    }
+}
+
+/// Where in the set a hook fires.
+#[derive(Debug, Clone, Copy)]
+pub struct HookContext<'a> {
+    /// `work`, `short_break` or `long_break`.
+    pub state: &'a str,
+    pub task: Option<&'a str>,
+    pub cycle: u16,
+    pub cycles: u16,
+    pub remaining: HumanDuration,
+}
+
+impl<'a> HookContext<'a> {
+    /// `phase` with `remaining` left.
+    pub fn new(phase: &'a Phase, remaining: HumanDuration) -> Self {
+        Self {
+            state: phase.kind.as_str(),
+            task: phase.task.as_deref(),
+            cycle: phase.cycle,
+            cycles: phase.cycles,
+            remaining,
+        }
+    }
+
+    /// Name and value pairs describing `event` at `at`, as used by webhook
+    /// templates.
+    pub fn variables(&self, event: &str, at: DateTime<Utc>) -> Vec<(&'static str, String)> {
+        vec![
+            ("event", event.to_string()),
+            ("state", self.state.to_string()),
+            ("task", self.task.unwrap_or_default().to_string()),
+            ("cycle", self.cycle.to_string()),
+            ("cycles", self.cycles.to_string()),
+            ("remaining", self.remaining.as_secs().to_string()),
+            ("at", at.to_rfc3339()),
+        ]
+    }
+
+    /// `event` at `at` as a JSON object.
+    pub fn to_json(&self, event: &str, at: DateTime<Utc>) -> String {
+        serde_json::json!({
+            "event": event,
+            "state": self.state,
+            "task": self.task,
+            "cycle": self.cycle,
+            "cycles": self.cycles,
+            "remaining": self.remaining.as_secs(),
+            "at": at.to_rfc3339(),
+        })
+        .to_string()
+    }
+}
+
+impl Hooks {
+    pub fn commands(&self, event: HookEvent) -> &[String] {
+        match event {
+            HookEvent::WorkStart => &self.work_start,
+            HookEvent::WorkEnd => &self.work_end,
+            HookEvent::BreakStart => &self.break_start,
+            HookEvent::BreakEnd => &self.break_end,
+            HookEvent::SetEnd => &self.set_end,
+            HookEvent::Paused => &self.paused,
+            HookEvent::Interrupted => &self.interrupted,
+        }
+    }
+
+    /// Runs the commands for `event` one after another. Failures and timeouts
+    /// are logged and never stop the timer.
+    ///
+    /// ```
+    /// use pompom::hooks::{HookContext, HookEvent, Hooks};
+    /// use pompom::HumanDuration;
+    ///
+    /// let out = std::env::temp_dir().join("pompom-hook-doctest");
+    /// let hooks = Hooks {
+    ///     work_start: vec![format!("echo $POMPOM_EVENT $POMPOM_CYCLE $POMPOM_TASK > {}", out.display())],
+    ///     ..Hooks::default()
+    /// };
+    /// let context = HookContext {
+    ///     state: "work",
+    ///     task: Some("Write report"),
+    ///     cycle: 2,
+    ///     cycles: 4,
+    ///     remaining: HumanDuration::from_mins(25),
+    /// };
+    /// hooks.run(HookEvent::WorkStart, &context);
+    /// assert_eq!(std::fs::read_to_string(&out).unwrap(), "work_start 2 Write report\n");
+    /// ```
+    pub fn run(&self, event: HookEvent, context: &HookContext<'_>) {
+        let commands = self.commands(event);
+        if commands.is_empty() {
+            return;
+        }
+        let sh = match Shell::new() {
+            Ok(sh) => sh,
+            Err(e) => {
+                log::warn!("Failed to run {event} hooks: {e}");
+                return;
+            }
+        };
+        for command in commands {
+            let cmd = cmd!(sh, "sh -c {command}")
+                .env("POMPOM_EVENT", event.as_str())
+                .env("POMPOM_STATE", context.state)
+                .env("POMPOM_TASK", context.task.unwrap_or_default())
+                .env("POMPOM_CYCLE", context.cycle.to_string())
+                .env("POMPOM_CYCLES", context.cycles.to_string())
+                .env("POMPOM_REMAINING", context.remaining.as_secs().to_string());
+            if let Err(e) = run_with_timeout(Command::from(cmd), self.timeout) {
+                log::warn!("{event} hook `{command}` {e}");
+            }
+        }
+    }
+}
+
+/// Runs the hooks of each event of the set, one event after the other.
+pub async fn follow(hooks: Hooks, mut events: SetEvents) {
+    let hooks = Arc::new(hooks);
+    while let Some(event) = events.next().await {
+        let Some((hook, _)) = HookEvent::of(&event) else {
+            continue;
+        };
+        if hooks.commands(hook).is_empty() {
+            continue;
+        }
+        let hooks = Arc::clone(&hooks);
+        // Commands are waited for, which would hold up other tasks.
+        let ran = tokio::task::spawn_blocking(move || {
+            if let Some((hook, context)) = HookEvent::of(&event) {
+                hooks.run(hook, &context);
+            }
+        });
+        if let Err(e) = ran.await {
+            log::warn!("Failed to run {hook} hooks: {e}");
+        }
+    }
+}
+
+/// Waits for `command`, killing it after `timeout`. The error describes how
+/// it failed, including what it wrote to stderr.
+fn run_with_timeout(mut command: Command, timeout: HumanDuration) -> Result<(), String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to start: {e}"))?;
+    // Read stderr on the side so a chatty command can't fill the pipe and stall.
+    let stderr = child.stderr.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut s = String::new();
+            let _ = pipe.read_to_string(&mut s);
+            s
+        })
+    });
+    let stderr = || {
+        stderr
+            .and_then(|handle| handle.join().ok())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .map_or_else(String::new, |s| format!(": {s}"))
+    };
+
+    let deadline = Instant::now() + timeout.as_std();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(format!("failed with {status}{}", stderr())),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                // Leave stderr be, children the command started may still hold it open.
+                return Err(format!("timed out after {timeout}"));
+            }
+            Ok(None) => std::thread::sleep(std::time::Duration::from_millis(20)),
+            Err(e) => return Err(format!("failed: {e}")),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! `pompom import`: merges an exported history, or a Taskwarrior export,
+//! into the local database.
+
+use std::path::Path;
+
+use clap::ValueEnum;
+use miette::{IntoDiagnostic, WrapErr};
+use tabled::{Table, Tabled};
+
+use crate::{
+    db::{Database, Session, Task},
+    error::PomodoroError,
+    export::Archive,
+    warrior,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// As written by `pompom export --format csv`.
+    Csv,
+    /// As written by `pompom export --format json`.
+    Json,
+    /// As written by `pompom export --format ndjson`.
+    Ndjson,
+    /// Pending tasks from Taskwarrior's `task export`.
+    Taskwarrior,
+}
+
+/// What [`merge`] did with each record of an archive.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportReport {
+    pub sessions: usize,
+    pub tasks: usize,
+    /// A line per added record, e.g. `task Write report`.
+    pub added: Vec<String>,
+    /// Records already present with identical contents.
+    pub duplicates: usize,
+    /// Records whose UUID is already present with different contents. The
+    /// local copy is kept.
+    pub conflicts: Vec<Conflict>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// `session` or `task`.
+    pub record: &'static str,
+    pub uuid: String,
+    /// Names of the fields that differ.
+    pub fields: Vec<&'static str>,
+}
+
+/// Adds the sessions and tasks of `archive` that aren't in `db` yet, matched
+/// by UUID. Nothing is written if any insert fails.
+///
+/// See [`preview`] for the same report without writing anything.
+///
+/// ```
+/// use chrono::Utc;
+/// use pompom::db::{Database, Session, SessionKind};
+/// use pompom::export::Archive;
+/// use pompom::import::merge;
+///
+/// let db = Database::open_in_memory().unwrap();
+/// let now = Utc::now();
+/// let session = Session::new(SessionKind::Work, now, now);
+/// db.insert_session(&session).unwrap();
+///
+/// let mut changed = session.clone();
+/// changed.note = Some("Edited on the laptop".into());
+/// let archive = Archive {
+///     sessions: vec![session, changed, Session::new(SessionKind::Work, now, now)],
+///     tasks: Vec::new(),
+/// };
+/// let report = merge(&db, &archive).unwrap();
+/// assert_eq!((report.sessions, report.duplicates), (1, 1));
+/// assert_eq!(report.conflicts[0].fields, ["note"]);
+/// assert_eq!(db.sessions().unwrap().len(), 2);
+/// ```
+pub fn merge(db: &Database, archive: &Archive) -> rusqlite::Result<ImportReport> {
+    db.in_transaction(|db| merge_into(db, archive))
+}
+
+/// What [`merge`] would do, rolled back afterwards.
+pub fn preview(db: &Database, archive: &Archive) -> rusqlite::Result<ImportReport> {
+    db.rolled_back(|db| merge_into(db, archive))
+}
+
+fn merge_into(db: &Database, archive: &Archive) -> rusqlite::Result<ImportReport> {
+    let mut report = ImportReport::default();
+    for session in &archive.sessions {
+        match db.session_by_uuid(&session.uuid)? {
+            None => {
+                db.insert_session(session)?;
+                report.sessions += 1;
+                report.added.push(format!(
+                    "session {} {}",
+                    session.kind,
+                    session.started_at.format("%Y-%m-%d %H:%M UTC")
+                ));
+            }
+            Some(local) => {
+                let fields = session_differences(&local, session);
+                report.record(fields, "session", &session.uuid);
+            }
+        }
+    }
+    for task in &archive.tasks {
+        match db.task_by_uuid(&task.uuid)? {
+            None => {
+                db.insert_task(task)?;
+                report.tasks += 1;
+                report.added.push(format!("task {}", task.title));
+            }
+            Some(local) => {
+                let fields = task_differences(&local, task);
+                report.record(fields, "task", &task.uuid);
+            }
+        }
+    }
+    Ok(report)
+}
+
+impl ImportReport {
+    fn record(&mut self, fields: Vec<&'static str>, record: &'static str, uuid: &str) {
+        if fields.is_empty() {
+            self.duplicates += 1;
+        } else {
+            self.conflicts.push(Conflict { record, uuid: uuid.to_string(), fields });
+        }
+    }
+}
+
+fn session_differences(a: &Session, b: &Session) -> Vec<&'static str> {
+    [
+        ("kind", a.kind != b.kind),
+        ("label", a.label != b.label),
+        ("started_at", a.started_at != b.started_at),
+        ("ended_at", a.ended_at != b.ended_at),
+        ("task", a.task != b.task),
+        ("note", a.note != b.note),
+        ("interruptions", a.interruptions != b.interruptions),
+        ("outcome", a.outcome != b.outcome),
+    ]
+    .into_iter()
+    .filter_map(|(name, differs)| differs.then_some(name))
+    .collect()
+}
+
+fn task_differences(a: &Task, b: &Task) -> Vec<&'static str> {
+    [
+        ("title", a.title != b.title),
+        ("estimate", a.estimate != b.estimate),
+        ("done", a.done != b.done),
+        ("created_at", a.created_at != b.created_at),
+    ]
+    .into_iter()
+    .filter_map(|(name, differs)| differs.then_some(name))
+    .collect()
+}
+
+/// Guesses the format of `path` from its extension, telling a Taskwarrior
+/// export from pompom's own JSON by its contents.
+fn detect_format(path: &Path, contents: &str) -> Option<ImportFormat> {
+    match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "csv" => Some(ImportFormat::Csv),
+        "json" if warrior::is_taskwarrior(contents) => Some(ImportFormat::Taskwarrior),
+        "json" => Some(ImportFormat::Json),
+        "ndjson" | "jsonl" => Some(ImportFormat::Ndjson),
+        _ => None,
+    }
+}
+
+#[derive(Tabled)]
+struct ConflictRow {
+    record: &'static str,
+    uuid: String,
+    #[tabled(rename = "differs in")]
+    fields: String,
+}
+
+/// `$ pompom import history.json`
+pub fn run_command(path: &Path, format: Option<ImportFormat>, dry_run: bool) -> miette::Result<()> {
+    let s = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+    let format = format
+        .or_else(|| detect_format(path, &s))
+        .ok_or_else(|| PomodoroError::UnknownFormat { path: path.to_path_buf() })?;
+    let archive = match format {
+        ImportFormat::Csv => Archive::from_csv(&s).into_diagnostic(),
+        ImportFormat::Json => Archive::from_json(&s).into_diagnostic(),
+        ImportFormat::Ndjson => Archive::from_ndjson(&s).into_diagnostic(),
+        ImportFormat::Taskwarrior => warrior::parse_taskwarrior(&s)
+            .map(|tasks| Archive { sessions: Vec::new(), tasks })
+            .into_diagnostic(),
+    }
+    .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+
+    let db = Database::open_history()?;
+    let report = if dry_run { preview(&db, &archive) } else { merge(&db, &archive) }
+        .into_diagnostic()
+        .wrap_err("Failed to import history")?;
+    let (imported, skipped) =
+        if dry_run { ("Would import", "skip") } else { ("Imported", "skipped") };
+    println!(
+        "{imported} {} sessions and {} tasks, {skipped} {} already present",
+        report.sessions, report.tasks, report.duplicates
+    );
+    if dry_run {
+        for added in &report.added {
+            println!("  + {added}");
+        }
+    }
+    if !report.conflicts.is_empty() {
+        println!("{} conflicts, kept the local copy:", report.conflicts.len());
+        let rows = report.conflicts.into_iter().map(|c| ConflictRow {
+            record: c.record,
+            uuid: c.uuid,
+            fields: c.fields.join(", "),
+        });
+        println!("{}", Table::new(rows));
+    }
+    Ok(())
+}
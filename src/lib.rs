@@ -5,215 +5,519 @@
 #![forbid(unsafe_code)]
 #![allow(unused)]
 
-mod error;
-
-use std::{error::Error, f32::consts::E, ops::Sub};
+pub mod api;
+pub mod calendar;
+pub mod clock;
+pub mod completions;
+pub mod config;
+pub mod db;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub mod dbus;
+mod doctor;
+mod duration;
+pub mod error;
+pub mod events;
+pub mod export;
+pub mod hooks;
+pub mod import;
+pub mod metrics;
+pub mod plan;
+pub mod progress;
+pub mod prompt;
+pub mod reminders;
+pub mod schedule;
+pub mod session;
+pub mod source;
+mod task;
+mod terminal;
+pub mod theme;
+mod timer;
+pub mod timers;
+pub mod warrior;
+pub mod webhook;
+mod wizard;
+
+use std::{error::Error, f32::consts::E, ops::Sub, sync::Arc};
 
 use chrono::{DateTime, Duration, Utc};
 use clap::{command, Parser, Subcommand};
 use clap_verbosity_flag::Verbosity;
-use dialoguer::{console::Style, theme::ColorfulTheme, Confirm, Input};
+use clock::Clock;
+use config::{Config, Profile};
+use db::{Database, Session, SessionKind, SessionOutcome};
 use error::{NotificationError, NotifyResult, PomodoroError};
-use miette::Diagnostic;
+use events::{Event, PhaseKind, SetEvents};
+use hooks::Hooks;
+use miette::{Diagnostic, IntoDiagnostic};
 use notify_rust::{Hint, Notification};
-use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use plan::Busy;
+use progress::Bars;
+use session::{Outcome, Timer};
+use source::{TaskRef, TaskSources};
+use theme::{ColorMode, Theme};
 use xshell::{cmd, Shell};
 
+pub use crate::duration::HumanDuration;
+
 #[derive(Debug)]
 pub struct App {
     pub cli: PomoFocusCli,
-    state_manager: StateManager,
+    /// Phase lengths resolved from the config profile and CLI flags.
+    profile: Profile,
+    /// Name of the config profile, `default` if none.
+    profile_name: String,
+    /// Meetings and blocked time that work phases are fitted around.
+    busy: Vec<Busy>,
+    /// Ask for a reflection note after each work phase.
+    reflect: bool,
+    sources: TaskSources,
+    hooks: Hooks,
+    webhooks: Vec<webhook::Webhook>,
+    /// Colors and markers of the phases.
+    theme: Theme,
+    bars: Bars,
+    reminders: reminders::Reminders,
+    metrics: Arc<metrics::Metrics>,
+    /// Runs the sets, and holds the status and commands shared with the HTTP
+    /// API and D-Bus.
+    timer: Timer,
+    /// [`Self::timer`] as [`timers::MAIN`], and the timers started by name
+    /// through the HTTP API.
+    timers: Arc<timers::Timers>,
+    /// Task picked with `pompom start --task`, and its title.
+    task: Option<(TaskRef, String)>,
 }
 
 impl App {
     pub fn new(cli: PomoFocusCli) -> Self {
-        let mut state_manager =
-            StateManager::new(PomofocusState::None).with_counter(None).with_max_count(Some(3));
-        state_manager.max_count = Some(cli.cycles);
+        let profile = cli.apply_flags(Profile::default());
+        let timer = Timer::new(profile.clone());
+        let timers = timers::Timers::new(timer.remote());
 
-        Self { cli, state_manager }
+        Self {
+            cli,
+            profile,
+            profile_name: "default".to_string(),
+            busy: Vec::new(),
+            reflect: false,
+            sources: TaskSources::default(),
+            hooks: Hooks::default(),
+            webhooks: Vec::new(),
+            theme: Theme::default(),
+            bars: Bars::default(),
+            reminders: reminders::Reminders::default(),
+            metrics: Arc::default(),
+            timer,
+            timers,
+            task: None,
+        }
     }
 
-    pub async fn run(&mut self) -> miette::Result<()> {
-        db::run().unwrap();
-        let notification_manager = match (&mut self.cli.command) {
-            Some(cmd) => match cmd {
-                CliCommands::Interactive | CliCommands::I => Some(dialoguer_main(&self.cli)?),
-            },
-            None => None,
-        };
-        if let Some(arg) = notification_manager {
-            self.cli.work_time = arg.work_time as u64;
-            self.cli.short_break_time = arg.short_break_time as u64;
-        };
+    /// Times phases with `clock` instead of the system clock, e.g. a
+    /// [`clock::ManualClock`] in tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.timer = self.timer.with_clock(clock);
+        self
+    }
 
-        if let Err(eee) =
-            self.run_timer_sequence().await.map(|_| ()).map_err(|_| NotificationError::Desktop)
-        {
-            unimplemented!()
+    pub async fn run(&mut self) -> miette::Result<()> {
+        self.cli.color().apply();
+        // These run before the config is loaded, doctor so it can report a
+        // broken one, and prompt and completions so they stay cheap.
+        match &self.cli.command {
+            Some(CliCommands::Doctor) => return doctor::run_command().await,
+            Some(CliCommands::Prompt { format }) => return prompt::run_command(format.as_deref()),
+            Some(CliCommands::Init { shell }) => {
+                prompt::init(*shell);
+                return Ok(());
+            }
+            Some(CliCommands::Completions { shell }) => return completions::run_command(*shell),
+            Some(CliCommands::Man { out_dir }) => return completions::man(out_dir.as_deref()),
+            Some(CliCommands::Complete { what }) => {
+                completions::candidates(*what);
+                return Ok(());
+            }
+            _ => {}
+        }
+        let config = Config::load()?;
+        self.profile = self.cli.resolve_profile(&config)?;
+        self.profile_name = self.cli.profile_name(&config).unwrap_or("default").to_string();
+        self.sources = config.sources.clone();
+        if let Some(CliCommands::Start { task: Some(task), .. }) = &self.cli.command {
+            let title = source::resolve(&self.sources, task)?;
+            self.task = Some((task.clone(), title));
+        }
+        self.reminders = config.reminders.clone();
+        if let Some(cmd) = &self.cli.command {
+            match cmd {
+                CliCommands::Interactive | CliCommands::I => {
+                    let Some(setup) = wizard::run(&config, &self.profile, &self.profile_name)?
+                    else {
+                        return Ok(());
+                    };
+                    self.profile = setup.profile;
+                    if let Some(name) = setup.profile_name {
+                        self.profile_name = name;
+                    }
+                    if setup.task.is_some() {
+                        self.task = setup.task;
+                    }
+                    self.reminders = setup.reminders;
+                }
+                CliCommands::Timer { duration, label, detach: true, .. } => {
+                    let name = label.as_deref().unwrap_or("timer");
+                    let timer = timers::NewTimer::countdown(*duration, label.clone());
+                    return timers::detach(&config, name, timer).await;
+                }
+                CliCommands::Timer { duration, label, log, .. } => {
+                    let (bars, clock) = (self.cli.bars(&config), self.timer.clock());
+                    return timer::countdown(*duration, label.as_deref(), *log, &bars, clock).await;
+                }
+                CliCommands::Stopwatch { label, log } => {
+                    let (bars, clock) = (self.cli.bars(&config), self.timer.clock());
+                    return timer::stopwatch(label.as_deref(), *log, &bars, clock).await;
+                }
+                CliCommands::Schedule { command } => return schedule::run_command(command),
+                CliCommands::Daemon => return self.run_daemon().await,
+                CliCommands::Task { command } => return task::run_command(command),
+                CliCommands::Plan { at } => {
+                    let now = self.timer.clock().now();
+                    return plan::run_command(&self.profile, &config, *at, now);
+                }
+                CliCommands::Export { format, range, timezone, output, dry_run } => {
+                    return export::run_command(
+                        *format,
+                        range.as_ref(),
+                        *timezone,
+                        output.as_ref(),
+                        *dry_run,
+                    );
+                }
+                CliCommands::Import { file, format, dry_run } => {
+                    return import::run_command(file, *format, *dry_run);
+                }
+                CliCommands::Start { detach: Some(name), .. } => {
+                    let task = self.task.as_ref().map(|(_, title)| title.clone());
+                    let timer = timers::NewTimer { profile: self.profile.clone(), task };
+                    return timers::detach(&config, name, timer).await;
+                }
+                CliCommands::List => return timers::list_command(&config).await,
+                CliCommands::Status { name } => return timers::status_command(&config, name).await,
+                CliCommands::Stop { name } => return timers::stop_command(&config, name).await,
+                // `Start`, and the commands already run above.
+                _ => {}
+            }
         }
+        check_working_hours(&config, self.timer.clock().now())?;
+        self.busy = upcoming_busy(&config, self.timer.clock().now());
+        self.reflect = config.reflect;
+        self.hooks = config.hooks.clone();
+        self.webhooks = config.webhooks.clone();
+        self.theme = config.theme.clone();
+        self.bars = self.cli.bars(&config);
+        self.serve_api(&config);
+        self.serve_dbus().await;
+
+        self.run_timer_sequence().await;
 
         Ok(())
     }
-    async fn run_timer_sequence(&mut self) -> NotifyResult {
-        // let m = &mut self.state_manager;
-        // dbg!(&self.cli);
-        // dbg!(&cycles_requested);
-        while self.state_manager.counter.unwrap_or(0) < self.cli.cycles {
-            &mut self.state_manager.set_next_state();
-            match &mut &mut self.state_manager.get_state() {
-                PomofocusState::Work => {
-                    let created_at = Utc::now();
-                    let work_time = self.cli.work_time;
-                    Self::prog(work_time);
-                    let work_expired_at = created_at + Duration::minutes(work_time as i64);
-                    let break_expired_at = work_expired_at + Duration::minutes(work_time as i64);
-                    notify_desktop(NotificationManager {
-                        id: None,
-                        description: "werk werk".into(),
-                        work_time: self.cli.work_time as u16,
-                        short_break_time: self.cli.short_break_time as u16,
-                        long_break_time: self.cli.long_break_time as u16,
-                        created_at,
-                        work_expired_at: Some(work_expired_at),
-                        break_expired_at: Some(break_expired_at),
-                        body: format!(
-                            "{}{}{}",
-                            created_at,
-                            work_time,
-                            work_expired_at.sub(created_at)
-                        ),
-                        icon: "alarm",
-                        timeout: 2000,
-                        appname: "pompom".into(),
-                    })
-                    .unwrap();
-                    &mut self.state_manager.next_counter();
+
+    /// Waits for each scheduled start and runs a full pomodoro set.
+    ///
+    /// The config is re-read every minute so `pompom schedule add` takes
+    /// effect without restarting the daemon.
+    async fn run_daemon(&mut self) -> miette::Result<()> {
+        let mut since = self.timer.clock().now();
+        let mut announced = None;
+        let config = Config::load()?;
+        self.serve_api(&config);
+        self.serve_dbus().await;
+        self.serve_metrics(&config);
+        loop {
+            let config = Config::load()?;
+            let Some((start, schedule)) = schedule::next_start(&config.schedules, since) else {
+                return Err(PomodoroError::NoSchedules.into());
+            };
+            let now = self.timer.clock().now();
+            if start <= now {
+                since = start;
+            } else {
+                if announced != Some(start) {
+                    let local = start.with_timezone(&schedule.timezone);
+                    println!(
+                        "Next pomodoro set starts at {}",
+                        local.format("%a %Y-%m-%d %H:%M %Z")
+                    );
+                    announced = Some(start);
                 }
-                PomofocusState::ShortBreak => {
-                    let work_time = self.cli.short_break_time;
-                    Self::prog(work_time);
+                let wake = start.min(now + Duration::minutes(1));
+                tokio::select! {
+                    _ = self.timer.clock().sleep_until(wake) => continue,
+                    Some(api::Command::Start) = self.timer.next_command() => {}
+                    _ = tokio::signal::ctrl_c() => return Ok(()),
                 }
-                PomofocusState::LongBreak => {}
-                PomofocusState::None => {}
             }
-            if self.state_manager.counter.unwrap() == self.cli.cycles {
-                self.state_manager.state = PomofocusState::LongBreak;
-                let work_time = self.cli.long_break_time;
-                Self::prog(work_time);
-                &mut self.state_manager.set_next_state();
+            self.busy = upcoming_busy(&config, self.timer.clock().now());
+            self.reflect = config.reflect;
+            self.sources = config.sources.clone();
+            self.hooks = config.hooks.clone();
+            self.webhooks = config.webhooks.clone();
+            self.theme = config.theme.clone();
+            self.bars = self.cli.bars(&config);
+            self.reminders = config.reminders.clone();
+            if self.run_timer_sequence().await == Outcome::Cancelled {
+                return Ok(());
             }
-            // if self.state_manager.state == PomofocusState::LongBreak {
-            // &mut self.state_manager.reset();
-            // &mut self.state_manager.reset_counter();
-            // }
-            // dbg!(&mut self.state_manager);
         }
-        // dbg!(&mut self.state_manager);
-        // dbg!(&self.cli);
+    }
 
-        Ok(())
+    /// Starts the HTTP API if the config has an `[api]` table.
+    fn serve_api(&self, config: &Config) {
+        let Some(api) = &config.api else {
+            return;
+        };
+        match api::serve(api, Arc::clone(&self.timers)) {
+            Ok(addr) => log::info!("HTTP API listening on http://{addr}"),
+            Err(e) => eprintln!("Warning: {e:?}"),
+        }
     }
 
-    fn prog(work_time: u64) {
-        let sh = Shell::new().expect("Shell::new() failed");
-        let len_duration: u64 = work_time * 60;
-        let duration_sec = std::time::Duration::from_millis(10);
-        // default to 1000ms as 1sec.
-        let pb = indicatif::ProgressBar::new(len_duration);
-        (0..len_duration).for_each(|_| {
-            pb.inc(1);
-            std::thread::sleep(duration_sec);
+    /// Serves `/metrics` if the config has a `[metrics]` table.
+    fn serve_metrics(&self, config: &Config) {
+        let Some(metrics) = &config.metrics else {
+            return;
+        };
+        match metrics::serve(metrics, self.timer.remote(), Arc::clone(&self.metrics)) {
+            Ok(addr) => log::info!("Serving metrics on http://{addr}/metrics"),
+            Err(e) => eprintln!("Warning: {e:?}"),
+        }
+    }
+
+    /// Exports the timer on the session bus, where there is one.
+    async fn serve_dbus(&self) {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        match dbus::serve(self.timer.remote()).await {
+            Ok(_connection) => log::info!("Serving {} on the session bus", dbus::NAME),
+            Err(e) => log::warn!("Failed to serve {} on the session bus: {e}", dbus::NAME),
+        }
+    }
+
+    /// Runs a pomodoro set, drawn in the terminal and followed by hooks,
+    /// webhooks, metrics, desktop notifications and the history until it
+    /// ends. `Ctrl+c` cancels it.
+    async fn run_timer_sequence(&mut self) -> Outcome {
+        let printer = printer::CliPrinter::stdout(self.theme.clone(), self.cli.color().choice());
+        let (prompter, drawing) =
+            terminal::spawn(self.timer.subscribe_set(), self.reflect, printer, self.bars.clone());
+        let mut subscribers = self.follow_set();
+        subscribers.push(drawing);
+        let timer = self
+            .timer
+            .clone()
+            .with_profile(self.profile.clone())
+            .with_busy(self.busy.clone())
+            .with_prompter(prompter);
+        let timer = match &self.task {
+            Some((_, title)) => timer.with_task(Some(title.clone())),
+            None => timer.with_task_queue(),
+        };
+        let session = timer.start();
+        let remote = timer.remote();
+        let cancel = tokio::spawn(async move {
+            // Refused before the first phase has started, so keep listening.
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if remote.send(api::Command::Cancel).is_ok() {
+                    return;
+                }
+            }
         });
-        pb.finish_with_message("Pomodoro finished! Take a break!");
+        let outcome = session.wait().await;
+        cancel.abort();
+        for subscriber in subscribers {
+            if let Err(e) = subscriber.await {
+                log::warn!("Event subscriber failed: {e}");
+            }
+        }
+        outcome
+    }
+
+    /// Every event from now on, for code that embeds the timer.
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<Event> {
+        self.timer.subscribe()
+    }
+
+    /// Spawns a task per built-in subscriber for the next set.
+    fn follow_set(&self) -> Vec<tokio::task::JoinHandle<()>> {
+        let timer = &self.timer;
+        vec![
+            tokio::spawn(hooks::follow(self.hooks.clone(), timer.subscribe_set())),
+            tokio::spawn(webhook::follow(self.webhooks.clone(), timer.subscribe_set())),
+            tokio::spawn(metrics::follow(
+                Arc::clone(&self.metrics),
+                self.profile_name.clone(),
+                timer.subscribe_set(),
+            )),
+            tokio::spawn(record_history(
+                self.task.clone(),
+                self.sources.clone(),
+                timer.subscribe_set(),
+            )),
+            tokio::spawn(notify_work_done(self.profile.clone(), timer.subscribe_set())),
+            tokio::spawn(prompt::follow(timer.subscribe_set())),
+            tokio::spawn(reminders::follow(self.reminders.clone(), timer.subscribe_set())),
+        ]
     }
-}
-pub async fn run(mut cli: PomoFocusCli) -> miette::Result<()> {
-    if let Some(arg) = match &cli.command {
-        Some(cmd) => match cmd {
-            CliCommands::Interactive | CliCommands::I => Some(dialoguer_main(&cli)?),
-        },
-        None => None,
-    } {
-        cli.work_time = arg.work_time as u64;
-        cli.short_break_time = arg.short_break_time as u64;
-    }
-    let _res = run_timer(cli).await.map(|_| ()).map_err(|_| NotificationError::Desktop);
-    Ok(())
 }
 
-async fn run_timer(cli: PomoFocusCli) -> NotifyResult {
-    let sh = Shell::new().expect("Shell::new() failed");
-    let len_duration: u64 = cli.work_time * 60;
-    let pb = indicatif::ProgressBar::new(len_duration);
-
-    let created_at: DateTime<Utc> = Utc::now();
-    let duration_sec = std::time::Duration::from_millis(1000); // default to 1000ms as 1sec.
-                                                               //
-    let arg_duration_work = Some(cli.work_time.to_string());
-    cmd!(sh, "echo {arg_duration_work...} minutes").run().unwrap();
-
-    let every_n_minute = |m: u64| m * 60;
-    let if_elapsed_spd_say = |i: &u64| match (i) % every_n_minute(5) == 0 && *i != 0 {
-        //TODO: Instead of spd-say, use rust_notify::Notification.
-        true => Some(format!("{} minutes over", i / 60)),
-        false => None,
-    };
+/// Adds the work phases of the set to the history that `pompom export`
+/// reads, with how they ended and the reflections written after them, and
+/// the completed ones to the source of `task`.
+async fn record_history(
+    task: Option<(TaskRef, String)>,
+    sources: TaskSources,
+    mut events: SetEvents,
+) {
+    let mut last = None;
+    while let Some(event) = events.next().await {
+        match event {
+            Event::PhaseEnded { phase, ended_at, pauses, skipped, .. }
+                if phase.kind == PhaseKind::Work =>
+            {
+                let outcome =
+                    if skipped { SessionOutcome::Skipped } else { SessionOutcome::Completed };
+                let session = Session::new(SessionKind::Work, phase.started_at, ended_at)
+                    .with_task(phase.task.clone())
+                    .with_interruptions(pauses)
+                    .with_outcome(outcome);
+                last = Some(session.uuid.clone());
+                log_work_phase(&session);
+                if skipped {
+                    continue;
+                }
+                if let Some((task, title)) = &task {
+                    let recorded =
+                        source::record_pomodoro(&sources, task, title, phase.started_at, ended_at);
+                    if let Err(e) = recorded {
+                        log::warn!("Failed to record pomodoro: {e:?}");
+                    }
+                }
+            }
+            Event::Interrupted { phase, ended_at, pauses, .. } if phase.kind == PhaseKind::Work => {
+                log_work_phase(
+                    &Session::new(SessionKind::Work, phase.started_at, ended_at)
+                        .with_task(phase.task)
+                        .with_interruptions(pauses)
+                        .with_outcome(SessionOutcome::Interrupted),
+                );
+            }
+            Event::Reflected { note, .. } => {
+                let Some(uuid) = &last else {
+                    continue;
+                };
+                let noted =
+                    Database::open_default().and_then(|db| db.set_session_note(uuid, &note));
+                if let Err(e) = noted {
+                    log::warn!("Failed to add reflection to history: {e}");
+                }
+            }
+            _ => {}
+        }
+    }
+}
 
-    // Main pomodoro progress loop!
-    (0..len_duration).for_each(|i: u64| {
-        pb.inc(1);
-        // TODO: Move conditional here to avoid invoking notification for `None`
-        // cases.
-        notify_elapsed_time(&sh, if_elapsed_spd_say(&i));
-        std::thread::sleep(duration_sec);
-    });
-    pb.finish_with_message("Pomodoro finished! Take a break!");
-
-    {
-        let args = vec!["5", "5"];
-        let arg_user_session_done: Option<String> = Some(format!("{} session done", &args[1]));
-        cmd!(sh, "spd-say -t female1 {arg_user_session_done...}").run().unwrap();
-        // `$ spd-say "'$val' session done"`
-    }
-    {
-        let work_expired_at = created_at + Duration::minutes(cli.work_time as i64);
-        let break_expired_at = work_expired_at + Duration::minutes(cli.short_break_time as i64);
-        let id = 1;
-        let args = NotificationManager {
-            id: Some(id),
-            description: String::from("Work session over"),
-            work_time: cli.work_time as u16,
-            short_break_time: cli.short_break_time as u16,
-            long_break_time: cli.long_break_time as u16,
+/// Shows a desktop notification when a work phase of the set is over, each
+/// in place of the one before.
+async fn notify_work_done(profile: Profile, mut events: SetEvents) {
+    let mut shown = None;
+    while let Some(event) = events.next().await {
+        let Event::PhaseEnded { phase, .. } = event else {
+            continue;
+        };
+        if phase.kind != PhaseKind::Work {
+            continue;
+        }
+        let created_at = phase.started_at;
+        let work_expired_at = created_at + phase.length.to_chrono();
+        let break_expired_at = work_expired_at + profile.short_break.to_chrono();
+        let notified = notify_desktop(NotificationManager {
+            id: shown,
+            description: "werk werk".into(),
+            work_time: profile.work,
+            short_break_time: profile.short_break,
+            long_break_time: profile.long_break,
             created_at,
             work_expired_at: Some(work_expired_at),
             break_expired_at: Some(break_expired_at),
-            body: format!("{:#?},{}", id, work_expired_at),
+            body: format!("{}{}{}", created_at, phase.length, work_expired_at.sub(created_at)),
             icon: "alarm",
             timeout: 2000,
             appname: "pompom",
-        };
+        });
+        match notified {
+            Ok(id) => shown = id,
+            Err(e) => log::warn!("{:?}", miette::Report::new(e)),
+        }
+    }
+}
+
+pub async fn run(cli: PomoFocusCli) -> miette::Result<()> {
+    App::new(cli).run().await
+}
+
+/// Warns, or refuses with `strict = true`, when `now` is outside the
+/// configured working hours.
+fn check_working_hours(config: &Config, now: DateTime<Utc>) -> miette::Result<()> {
+    let Some(hours) = &config.working_hours else {
+        return Ok(());
+    };
+    if hours.contains(now) {
+        return Ok(());
+    }
+    let outside = PomodoroError::OutsideWorkingHours {
+        hours: format!(
+            "{}-{} {}",
+            hours.start.format("%H:%M"),
+            hours.end.format("%H:%M"),
+            hours.timezone
+        ),
+    };
+    if hours.strict {
+        return Err(outside.into());
+    }
+    eprintln!("Warning: {outside}");
+    Ok(())
+}
 
-        notify_desktop(args)
+/// Adds a work phase, however it ended, to the history that `pompom export` reads.
+fn log_work_phase(session: &Session) {
+    if let Err(e) = Database::open_default().and_then(|db| db.insert_session(session)) {
+        log::warn!("Failed to log work phase to history: {e}");
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Blocked time and calendar meetings for the day of `now` and the next. A
+/// calendar that can't be read only costs the overlap warning, so it doesn't
+/// stop the timer.
+fn upcoming_busy(config: &Config, now: DateTime<Utc>) -> Vec<Busy> {
+    let tz = schedule::local_timezone();
+    let today = now.with_timezone(&tz).date_naive();
+    calendar::load_busy(config, tz, today, 2).unwrap_or_else(|e| {
+        eprintln!("Warning: {e:?}");
+        Vec::new()
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum PomofocusState {
     Work,
     ShortBreak,
     LongBreak,
     /// Default session state between other states.
+    #[default]
     None,
 }
 
-impl Default for PomofocusState {
-    fn default() -> Self {
-        PomofocusState::None
-    }
-}
-
 /// intervals = 3
 /// work state 1
 /// short break state
@@ -279,17 +583,15 @@ impl StateManager {
         self
     }
     fn check_next_state(&mut self) -> PomofocusState {
-        let next_state = match self.state {
+        match self.state {
             PomofocusState::Work => PomofocusState::ShortBreak,
-            PomofocusState::ShortBreak => PomofocusState::Work,
+            PomofocusState::ShortBreak | PomofocusState::None => PomofocusState::Work,
             PomofocusState::LongBreak => PomofocusState::None,
-            PomofocusState::None => PomofocusState::Work,
-        };
-        next_state
+        }
         // self.state = next_state;
     }
     fn get_state(&mut self) -> PomofocusState {
-        self.state.to_owned()
+        self.state.clone()
     }
     fn is_state_longbreak(&mut self) -> bool {
         match self.get_state() {
@@ -312,7 +614,6 @@ impl StateManager {
             PomofocusState::Work => {
                 self.counter = Some(self.counter.unwrap_or(0) + 1);
             }
-            PomofocusState::ShortBreak | PomofocusState::LongBreak => (), /* 1 --> THIS */
             // PomofocusState::None
             // => { self.counter =
             // None; } //1 -->
@@ -325,8 +626,7 @@ impl StateManager {
 
     fn reset(&mut self) {
         let is_curr_longbreak = self.is_state_longbreak();
-        let is_next_none =
-            if let PomofocusState::None = self.check_next_state() { true } else { false };
+        let is_next_none = matches!(self.check_next_state(), PomofocusState::None);
         assert_eq!(is_curr_longbreak, is_next_none); // then reset.
                                                      //
         self.state = PomofocusState::None;
@@ -339,32 +639,13 @@ impl StateManager {
 
     pub fn set_next_state(&mut self) {
         self.state = self.check_next_state();
-        self.state_message().expect("StateManager::set_next_state: state_message() failed");
-    }
-
-    fn state_message(&self) -> Result<String, Box<dyn std::error::Error>> {
-        match self.state {
-            PomofocusState::Work => {
-                let msg = "💪🏻";
-                printer::CliPrinter::new(Some(msg)).write_green()?;
-                Ok(String::from(msg))
-            }
-            PomofocusState::ShortBreak => {
-                let msg = "💤🏻";
-                printer::CliPrinter::new(Some(msg)).write_yellow()?;
-                Ok(String::from(msg))
-            }
-            PomofocusState::LongBreak => {
-                let msg = "💤🏻💤🏻💤🏻";
-                printer::CliPrinter::new(Some(msg)).write_red()?;
-                Ok(String::from(msg))
-            }
-            PomofocusState::None => Ok(String::new()),
-        }
     }
 }
 
-fn notify_desktop(args: NotificationManager) -> Result<(), NotificationError> {
+/// Shows `args` as a desktop notification, in place of the one `args.id`
+/// was shown with if any. Returns the id it was shown with, where the
+/// notification server gives one.
+fn notify_desktop(args: NotificationManager) -> Result<Option<u32>, NotificationError> {
     let mut notification = Notification::new();
     let notification = notification
         .summary(&args.description)
@@ -378,7 +659,21 @@ fn notify_desktop(args: NotificationManager) -> Result<(), NotificationError> {
 
     #[cfg(target_os = "linux")]
     notification.hint(Hint::Category("im.received".to_owned())).sound_name("message-new-instant");
-    notification.show().map(|_| ()).map_err(NotificationError::Desktop)
+    if let Some(id) = args.id {
+        notification.id(id);
+    }
+    let shown = notification.show().map_err(NotificationError::Desktop)?;
+    Ok(shown_id(&shown))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn shown_id(handle: &notify_rust::NotificationHandle) -> Option<u32> {
+    Some(handle.id())
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn shown_id<T>(_handle: &T) -> Option<u32> {
+    None
 }
 
 #[derive(Debug, Subcommand, PartialEq, Clone)]
@@ -390,12 +685,187 @@ pub enum CliCommands {
     /// Usage: $ pompom interactive
     #[command(arg_required_else_help = false)]
     I,
+
+    /// Usage: $ pompom start --task @todo:3
+    ///
+    /// Runs a pomodoro set like plain `pompom`, spending every work phase on
+    /// one task.
+    Start {
+        /// A queued task number, or `@todo:N` / `@org:N` for a task in a
+        /// `todo.txt` or Org source.
+        #[arg(long)]
+        task: Option<source::TaskRef>,
+        /// Runs the set in `pompom daemon` as the timer NAME instead of in
+        /// this terminal.
+        #[arg(long, value_name = "NAME")]
+        detach: Option<String>,
+    },
+
+    /// Usage: $ pompom timer 10m "tea"
+    ///
+    /// Counts down a single timer, e.g. `1h30m`, `90s` or `25` (minutes).
+    Timer {
+        duration: HumanDuration,
+        label: Option<String>,
+        /// Log the finished timer to history.
+        #[arg(long, conflicts_with = "detach")]
+        log: bool,
+        /// Runs the timer in `pompom daemon`, named after its label.
+        #[arg(long)]
+        detach: bool,
+    },
+
+    /// Usage: $ pompom stopwatch
+    ///
+    /// Counts up until stopped with `Ctrl+c`.
+    Stopwatch {
+        label: Option<String>,
+        /// Log the stopped stopwatch to history.
+        #[arg(long)]
+        log: bool,
+    },
+
+    /// Usage: $ pompom schedule list|add|rm
+    ///
+    /// Manages the start times used by `pompom daemon`.
+    Schedule {
+        #[command(subcommand)]
+        command: schedule::ScheduleCommands,
+    },
+
+    /// Usage: $ pompom daemon
+    ///
+    /// Runs in the foreground and starts a pomodoro set at each scheduled time.
+    Daemon,
+
+    /// Usage: $ pompom list
+    ///
+    /// Lists the timers `pompom daemon` runs, through its HTTP API.
+    List,
+
+    /// Usage: $ pompom status standup
+    ///
+    /// Shows the phase and time left of a timer of `pompom daemon`.
+    Status {
+        #[arg(default_value = timers::MAIN)]
+        name: String,
+    },
+
+    /// Usage: $ pompom stop standup
+    ///
+    /// Stops a timer of `pompom daemon`.
+    Stop { name: String },
+
+    /// Usage: $ pompom task add|list|done|rm
+    ///
+    /// Manages the queue of tasks that `pompom plan` slots into work phases.
+    Task {
+        #[command(subcommand)]
+        command: task::TaskCommands,
+    },
+
+    /// Usage: $ pompom plan --at 14:00
+    ///
+    /// Prints a timeline of the day's pomodoros for the queued tasks.
+    Plan {
+        /// Plan as if starting at this time today instead of now.
+        #[arg(long, value_parser = schedule::parse_time)]
+        at: Option<chrono::NaiveTime>,
+    },
+
+    /// Usage: $ pompom export --format ics --range 2026-10-01..2026-10-31
+    ///
+    /// Writes the session history. `ics` and `timewarrior` have completed work
+    /// phases only, `csv`, `json` and `ndjson` carry sessions, tasks,
+    /// interruptions and notes.
+    Export {
+        #[arg(long, value_enum, default_value_t = export::ExportFormat::Ics)]
+        format: export::ExportFormat,
+        /// Dates to include, e.g. `2026-10-01..2026-10-31`, `2026-10-01..` or `..2026-10-31`.
+        #[arg(long)]
+        range: Option<export::DateRange>,
+        /// Write times in this IANA time zone instead of UTC.
+        #[arg(long)]
+        timezone: Option<chrono_tz::Tz>,
+        /// Write to a file instead of stdout, or instead of the Timewarrior
+        /// database for `timewarrior`.
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// Print what would be written without writing it.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Usage: $ pompom import history.json
+    ///
+    /// Merges a `csv`, `json` or `ndjson` export, or pending tasks from a
+    /// Taskwarrior `task export`, skipping records already present and
+    /// reporting ones that differ.
+    Import {
+        file: std::path::PathBuf,
+        /// Format of the file. Guessed from its extension by default.
+        #[arg(long, value_enum)]
+        format: Option<import::ImportFormat>,
+        /// Show what would be imported without changing anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Usage: $ pompom doctor
+    ///
+    /// Checks notifications, speech, the config, the history database, the
+    /// daemon, colors and the time zone, with a fix for each problem found.
+    Doctor,
+
+    /// Usage: $ pompom prompt
+    ///
+    /// Prints a short segment like `🍅 12:34` for a shell prompt or the tmux
+    /// status line, or nothing when no phase is running.
+    Prompt {
+        /// Segment to print, with `{icon}`, `{phase}` and `{remaining}`.
+        #[arg(long)]
+        format: Option<String>,
+    },
+
+    /// Usage: $ eval "$(pompom init bash)"
+    ///
+    /// Prints a snippet that puts `pompom prompt` in the prompt of the shell,
+    /// or the status line of tmux.
+    Init {
+        #[arg(value_enum)]
+        shell: prompt::Shell,
+    },
+
+    /// Usage: $ pompom completions zsh > ~/.zfunc/_pompom
+    ///
+    /// Prints a completion script. The bash, zsh and fish ones also complete
+    /// task numbers and profile names.
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Usage: $ pompom man > pompom.1
+    ///
+    /// Prints the man page, or writes one page per command to a directory.
+    Man {
+        /// Directory to write `pompom.1`, `pompom-start.1` and so on to.
+        #[arg(long)]
+        out_dir: Option<std::path::PathBuf>,
+    },
+
+    /// Lists what `--task` or `--profile` can be, for completion scripts.
+    #[command(hide = true)]
+    Complete {
+        #[arg(value_enum)]
+        what: completions::Candidates,
+    },
 }
 
-const DEFAULT_WORK_TIME: u64 = 15;
-const DEFAULT_SHORT_BREAK_TIME: u64 = 5;
-const DEFAULT_LONG_BREAK_TIME: u64 = 25;
-const DEFAULT_WORK_CYCLES: u16 = 3;
+pub(crate) const DEFAULT_WORK_TIME: HumanDuration = HumanDuration::from_mins(15);
+pub(crate) const DEFAULT_SHORT_BREAK_TIME: HumanDuration = HumanDuration::from_mins(5);
+pub(crate) const DEFAULT_LONG_BREAK_TIME: HumanDuration = HumanDuration::from_mins(25);
+pub(crate) const DEFAULT_WORK_CYCLES: u16 = 3;
 
 /// `pompom` CLI terminal flags with settings.
 /// By default, this will only report errors.
@@ -415,23 +885,38 @@ pub struct PomoFocusCli {
     #[clap(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
 
-    /// Sets the length of work time period in minutes.
-    #[arg(short = 'w', long = "work", default_value_t = DEFAULT_WORK_TIME)]
-    work_time: u64,
+    /// Uses the phase lengths of a `[profiles.<name>]` table in the config
+    /// file. Other flags override single values.
+    #[arg(short = 'p', long = "profile")]
+    profile: Option<String>,
+
+    /// Sets the length of work time period, e.g. `25m30s`, `1h` or `25`
+    /// (minutes). Default: 15m.
+    #[arg(short = 'w', long = "work")]
+    work_time: Option<HumanDuration>,
 
-    /// Sets the length of short break in minutes after each work period
-    /// elapses.
-    #[arg(short = 's', long = "shortbreak", default_value_t = DEFAULT_SHORT_BREAK_TIME)]
-    short_break_time: u64,
+    /// Sets the length of short break after each work period elapses, e.g.
+    /// `90s` or `5` (minutes). Default: 5m.
+    #[arg(short = 's', long = "shortbreak")]
+    short_break_time: Option<HumanDuration>,
 
-    /// Sets the length of long break in minutes after all work period
-    /// completes.
-    #[arg(short = 'l', long = "longbreak", default_value_t = DEFAULT_LONG_BREAK_TIME)]
-    long_break_time: u64,
+    /// Sets the length of long break after all work period completes, e.g.
+    /// `20m` or `20` (minutes). Default: 25m.
+    #[arg(short = 'l', long = "longbreak")]
+    long_break_time: Option<HumanDuration>,
 
     /// Sets the count of work cycles before a long break starts. Default: 3.
-    #[arg(short = 'c', long = "cycles", default_value_t = DEFAULT_WORK_CYCLES)]
-    cycles: u16,
+    #[arg(short = 'c', long = "cycles")]
+    cycles: Option<u16>,
+
+    /// When to color output. `auto` does unless `NO_COLOR` is set or stdout
+    /// isn't a terminal.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    color: ColorMode,
+
+    /// Draws each phase on one short line, for small panes.
+    #[arg(long, global = true)]
+    compact: bool,
 }
 
 impl Default for PomoFocusCli {
@@ -443,22 +928,68 @@ impl PomoFocusCli {
     pub fn new() -> Self {
         Self {
             command: None,
-            work_time: DEFAULT_WORK_TIME,
-            short_break_time: DEFAULT_SHORT_BREAK_TIME,
-            long_break_time: DEFAULT_LONG_BREAK_TIME,
+            profile: None,
+            work_time: None,
+            short_break_time: None,
+            long_break_time: None,
             verbose: Verbosity::new(1, 0),
-            cycles: DEFAULT_WORK_CYCLES,
+            cycles: None,
+            color: ColorMode::Auto,
+            compact: false,
+        }
+    }
+
+    /// Progress bars of the `[progress]` table and theme of `config`, compact
+    /// with `--compact`.
+    pub fn bars(&self, config: &Config) -> Bars {
+        let mut progress = config.progress.clone();
+        progress.compact |= self.compact;
+        Bars::new(progress, config.theme.clone())
+    }
+
+    /// When to color output, see [`ColorMode`].
+    pub fn color(&self) -> ColorMode {
+        self.color
+    }
+
+    /// Level of the log messages shown, set by `-q` and `-v`.
+    pub fn log_level_filter(&self) -> log::LevelFilter {
+        self.verbose.log_level_filter()
+    }
+
+    /// Name of the `--profile`, or of the config's default profile.
+    pub fn profile_name<'a>(&'a self, config: &'a Config) -> Option<&'a str> {
+        self.profile.as_deref().or(config.profile.as_deref())
+    }
+
+    /// The `--profile` (or the config's default profile) with any explicit
+    /// time and cycle flags applied on top.
+    pub fn resolve_profile(&self, config: &Config) -> miette::Result<Profile> {
+        let base = match self.profile_name(config) {
+            Some(name) => config.profile(name)?,
+            None => Profile::default(),
+        };
+        Ok(self.apply_flags(base))
+    }
+
+    fn apply_flags(&self, profile: Profile) -> Profile {
+        Profile {
+            work: self.work_time.unwrap_or(profile.work),
+            short_break: self.short_break_time.unwrap_or(profile.short_break),
+            long_break: self.long_break_time.unwrap_or(profile.long_break),
+            cycles: self.cycles.unwrap_or(profile.cycles),
         }
     }
 }
 
 #[derive(Debug)]
 pub struct NotificationManager {
-    id: Option<u16>,
+    /// Id of an earlier notification this one replaces.
+    id: Option<u32>,
     description: String,
-    work_time: u16,
-    short_break_time: u16,
-    long_break_time: u16,
+    work_time: HumanDuration,
+    short_break_time: HumanDuration,
+    long_break_time: HumanDuration,
     created_at: DateTime<Utc>,
     work_expired_at: Option<DateTime<Utc>>,
     break_expired_at: Option<DateTime<Utc>>,
@@ -469,65 +1000,23 @@ pub struct NotificationManager {
     appname: &'static str,
 }
 
-// TODO: Return or modify or parse Cli clap args.
-pub fn dialoguer_main(cli: &PomoFocusCli) -> miette::Result<NotificationManager> {
-    let created_at: DateTime<Utc> = Utc::now();
-
-    let theme =
-        ColorfulTheme { values_style: Style::new().yellow().dim(), ..ColorfulTheme::default() };
-    println!("Welcome to the setup wizard");
-
-    let id = 1;
-    let work_expired_at = created_at + Duration::minutes(cli.work_time as i64);
-    let break_expired_at = work_expired_at + Duration::minutes(cli.short_break_time as i64);
-
-    let mut args = NotificationManager {
-        id: Some(id),
-        description: String::from("Work session over"),
-        work_time: cli.work_time as u16,
-        short_break_time: cli.short_break_time as u16,
-        long_break_time: cli.long_break_time as u16,
-        created_at,
-        work_expired_at: Some(work_expired_at),
-        break_expired_at: Some(break_expired_at),
-        body: format!("{:#?},{}", id, work_expired_at),
-        icon: "alarm",
-        timeout: 2000,
-        appname: "pompom",
-    };
-
-    args.work_time = Input::with_theme(&theme).with_prompt("Enter work time").interact().unwrap();
-
-    if Confirm::with_theme(&theme)
-        .with_prompt("Do you want to edit break times?")
-        .interact()
-        .unwrap()
-    {
-        args.short_break_time = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Enter short break time")
-            .interact()
-            .unwrap();
-        args.long_break_time = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Enter long break time")
-            .interact()
-            .unwrap();
-        // return Ok(None);
-    }
-
-    Ok(args)
-}
-
-/// `$ spd-say "'$val' session done"`
-fn notify_elapsed_time(sh: &Shell, arg_curr_progress: Option<String>) {
-    match arg_curr_progress {
-        Some(arg) => {
-            let arg = Some(arg);
-            cmd!(sh, "spd-say {arg...}").run().unwrap();
+/// `$ spd-say "'$val' minutes over"`
+fn notify_elapsed_time(arg_curr_progress: Option<String>) {
+    if let Some(arg) = arg_curr_progress {
+        if let Err(e) = speak(&arg, None) {
+            log::warn!("{:?}", miette::Report::new(e));
         }
-        None => (),
     }
 }
 
+/// Says `text` out loud with `spd-say`, in `voice` if given.
+fn speak(text: &str, voice: Option<&str>) -> NotifyResult {
+    let speech = |source| NotificationError::Speech { text: text.to_string(), source };
+    let sh = Shell::new().map_err(speech)?;
+    let voice = voice.map(|voice| ["-t", voice]).into_iter().flatten();
+    cmd!(sh, "spd-say {voice...} {text}").run().map_err(speech)
+}
+
 //-----------------------------------------------------------------------------
 
 #[allow(unused)]
@@ -543,7 +1032,7 @@ mod tbd {
     }
 
     fn get_feedback() {
-        if let Some(rv) = dialoguer::Editor::new().edit("The work session was ...").unwrap() {
+        if let Ok(Some(rv)) = dialoguer::Editor::new().edit("The work session was ...") {
             println!("{}", rv);
         } else {
             println!("Abort!");
@@ -565,60 +1054,82 @@ mod tbd {
     }
 }
 
-#[allow(unused)]
 pub mod printer {
-    use std::{
-        io,
-        io::{BufRead, BufReader, Read, Write},
-    };
+    //! Colored lines on stdout, or in memory for tests.
+
+    use std::io::{self, Write};
 
-    use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+    use termcolor::{Buffer, Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
-    // TODO: Add stdio stdout feature.
-    // pub struct CliPrinter<W:Write,R:Read>(W,R);
-    #[derive(Debug, Default)]
+    use crate::{events::PhaseKind, theme::Theme};
+
+    /// Where a [`CliPrinter`] writes.
+    #[derive(Debug)]
+    enum Target {
+        Stdout(StandardStream),
+        Memory(Buffer),
+    }
+
+    /// Writes the line each phase starts with, in the colors and markers of
+    /// its [`Theme`].
+    ///
+    /// ```
+    /// use pompom::{events::PhaseKind, printer::CliPrinter, theme::{Glyphs, Theme}};
+    ///
+    /// let theme = Theme { glyphs: Glyphs::Ascii, ..Theme::default() };
+    /// let mut printer = CliPrinter::memory(theme, false);
+    /// printer.phase(PhaseKind::Work).unwrap();
+    /// printer.phase(PhaseKind::LongBreak).unwrap();
+    /// assert_eq!(printer.contents().as_deref(), Some("[work]\n[long break]\n"));
+    /// ```
+    #[derive(Debug)]
     pub struct CliPrinter {
-        msg: &'static str,
-        stdin: Option<StandardStream>,
-        stdout: Option<StandardStream>,
-        bufwtr: Option<BufferWriter>,
-        /// Write colored text to memory.
-        bufmem: Option<termcolor::Buffer>,
+        theme: Theme,
+        target: Target,
+    }
+
+    impl Default for CliPrinter {
+        fn default() -> Self {
+            Self::stdout(Theme::default(), ColorChoice::Auto)
+        }
     }
 
     impl CliPrinter {
-        pub fn new(msg: Option<&'static str>) -> Self {
-            Self {
-                msg: if let Some(s) = msg { s } else { "" },
-                stdin: None,
-                stdout: None,
-                bufwtr: None,
-                bufmem: None,
-            }
+        pub fn stdout(theme: Theme, choice: ColorChoice) -> Self {
+            Self { theme, target: Target::Stdout(StandardStream::stdout(choice)) }
         }
-        pub fn write_green(&mut self) -> io::Result<()> {
-            let mut stdout = StandardStream::stdout(ColorChoice::Always);
-            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
-            writeln!(&mut stdout, "{0}", self.msg)
+
+        /// Writes to memory, with color escapes if `color`, to be read back with
+        /// [`CliPrinter::contents`].
+        pub fn memory(theme: Theme, color: bool) -> Self {
+            let buffer = if color { Buffer::ansi() } else { Buffer::no_color() };
+            Self { theme, target: Target::Memory(buffer) }
         }
 
-        pub fn write_green_buf(&mut self) -> io::Result<()> {
-            let mut bufwtr = BufferWriter::stderr(ColorChoice::Always);
-            let mut buffer = bufwtr.buffer();
-            buffer.set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
-            writeln!(&mut buffer, "{0}", self.msg)?;
-            bufwtr.print(&buffer)
+        /// What was written, if it was written to memory.
+        pub fn contents(&self) -> Option<String> {
+            match &self.target {
+                Target::Stdout(_) => None,
+                Target::Memory(buffer) => Some(String::from_utf8_lossy(buffer.as_slice()).into()),
+            }
         }
 
-        pub fn write_yellow(&mut self) -> io::Result<()> {
-            let mut stdout = StandardStream::stdout(ColorChoice::Always);
-            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
-            writeln!(&mut stdout, "{0}", self.msg)
+        /// Writes the line a phase of `kind` starts with.
+        pub fn phase(&mut self, kind: PhaseKind) -> io::Result<()> {
+            let color = self.theme.color(kind);
+            self.line(self.theme.glyphs.marker(kind), Some(color))
         }
-        pub fn write_red(&mut self) -> io::Result<()> {
-            let mut stdout = StandardStream::stdout(ColorChoice::Always);
-            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)))?;
-            writeln!(&mut stdout, "{0}", self.msg)
+
+        /// Writes `text` on a line of its own, in `color` if colors are on.
+        pub fn line(&mut self, text: &str, color: Option<Color>) -> io::Result<()> {
+            let out: &mut dyn WriteColor = match &mut self.target {
+                Target::Stdout(stdout) => stdout,
+                Target::Memory(buffer) => buffer,
+            };
+            out.set_color(ColorSpec::new().set_fg(color))?;
+            write!(out, "{text}")?;
+            out.reset()?;
+            writeln!(out)
         }
     }
 }
@@ -718,41 +1229,3 @@ pub mod printer {
 
     */
 } */
-
-pub mod db {
-    use rusqlite::{Connection, Result, Statement};
-
-    #[derive(Debug)]
-    struct Person {
-        id: i32,
-        name: String,
-        data: Option<Vec<u8>>,
-    }
-
-    pub fn run() -> Result<()> {
-        let conn = Connection::open_in_memory()?;
-
-        conn.execute(
-            "CREATE TABLE person (
-                id      INTEGER PRIMARY KEY,
-                name    TEXT NOT NULL,
-                data    BLOB
-                )",
-            (), // empty list of parameters
-        )?;
-
-        let me = Person { id: 0, name: "John".to_string(), data: None };
-        conn.execute("INSERT INTO person (name, data) VALUES (?1, ?2)", (&me.name, &me.data))?;
-
-        let mut stmt: Statement<'_> = conn.prepare("SELECT id, name, data FROM person")?;
-        let person_iter = stmt.query_map([], |row| {
-            Ok(Person { id: row.get(0)?, name: row.get(1)?, data: row.get(2)? })
-        })?;
-
-        for person in person_iter {
-            println!("Found person {:?}", person?);
-        }
-
-        Ok(())
-    }
-}
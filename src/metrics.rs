@@ -0,0 +1,270 @@
+//! Prometheus metrics of `pompom daemon`, served on `127.0.0.1` when the
+//! config has a `[metrics]` table.
+//!
+//! ```toml
+//! [metrics]
+//! port = 9464
+//! ```
+//!
+//! Counters start from zero when the daemon does, as Prometheus expects.
+
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt::Write,
+    net::SocketAddr,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use hyper::{
+    header::{self, HeaderValue},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use miette::{IntoDiagnostic, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{Remote, Status},
+    events::{Event, PhaseKind, SetEvents},
+    schedule, HumanDuration,
+};
+
+/// The `[metrics]` table of the config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Port on `127.0.0.1`, `0` picks a free one.
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+fn default_port() -> u16 {
+    9464
+}
+
+/// Counts per task and profile, for as long as the process runs.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    counts: Mutex<BTreeMap<(String, String), Counts>>,
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    /// When each work phase was completed.
+    completed: Vec<DateTime<Utc>>,
+    focus_secs: u64,
+    interruptions: u64,
+    abandoned: u64,
+}
+
+impl Metrics {
+    /// Counts a work phase that ended at `at`. It was `focused` for that long
+    /// and paused `pauses` times, and `completed` unless it was skipped or
+    /// stopped.
+    pub fn work_phase(
+        &self,
+        task: Option<&str>,
+        profile: &str,
+        at: DateTime<Utc>,
+        focused: HumanDuration,
+        pauses: u32,
+        completed: bool,
+    ) {
+        self.update(task, profile, |counts| {
+            if completed {
+                counts.completed.push(at);
+            } else {
+                counts.abandoned += 1;
+            }
+            counts.focus_secs += focused.as_secs();
+            counts.interruptions += u64::from(pauses);
+        });
+    }
+
+    /// Counts a break that was skipped or stopped before its end.
+    pub fn abandoned_break(&self, profile: &str) {
+        self.update(None, profile, |counts| counts.abandoned += 1);
+    }
+
+    /// Counts `event` if it ends a phase run with `profile`.
+    pub fn record(&self, profile: &str, event: &Event) {
+        match event {
+            Event::PhaseEnded { phase, ended_at, elapsed, pauses, skipped: false }
+                if phase.kind == PhaseKind::Work =>
+            {
+                self.work_phase(phase.task.as_deref(), profile, *ended_at, *elapsed, *pauses, true);
+            }
+            Event::PhaseEnded { phase, ended_at, elapsed, pauses, skipped: true }
+            | Event::Interrupted { phase, ended_at, elapsed, pauses, .. } => {
+                if phase.kind == PhaseKind::Work {
+                    let task = phase.task.as_deref();
+                    self.work_phase(task, profile, *ended_at, *elapsed, *pauses, false);
+                } else {
+                    self.abandoned_break(profile);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&self, task: Option<&str>, profile: &str, f: impl FnOnce(&mut Counts)) {
+        let mut counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        let key = (task.unwrap_or_default().to_string(), profile.to_string());
+        f(counts.entry(key).or_default());
+    }
+
+    /// The metrics in the Prometheus text format, with "today" in `tz`.
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use pompom::{api::Status, metrics::Metrics, HumanDuration};
+    ///
+    /// let metrics = Metrics::default();
+    /// let now = Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+    /// let focused = HumanDuration::from_mins(25);
+    /// metrics.work_phase(Some("Write report"), "deep", now, focused, 1, true);
+    ///
+    /// let text = metrics.render(&Status::default(), chrono_tz::UTC, now);
+    /// assert!(text.contains(r#"pompom_state{state="idle"} 1"#));
+    /// assert!(text.contains(r#"pompom_pomodoros_today{task="Write report",profile="deep"} 1"#));
+    /// assert!(text.contains(r#"pompom_focus_seconds_total{task="Write report",profile="deep"} 1500"#));
+    /// ```
+    pub fn render(&self, status: &Status, tz: Tz, now: DateTime<Utc>) -> String {
+        let today = now.with_timezone(&tz).date_naive();
+        let counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "pompom_state",
+            "gauge",
+            "The timer's current state, 1 for the active one.",
+        );
+        for state in ["idle", "work", "short_break", "long_break"] {
+            let active = u8::from(status.state == state);
+            let _ = writeln!(out, "pompom_state{{state=\"{state}\"}} {active}");
+        }
+        header(&mut out, "pompom_paused", "gauge", "Whether the current phase is paused.");
+        let _ = writeln!(out, "pompom_paused {}", u8::from(status.paused));
+        header(&mut out, "pompom_remaining_seconds", "gauge", "Seconds left in the current phase.");
+        let _ = writeln!(out, "pompom_remaining_seconds {}", status.remaining);
+
+        let completed_today = |c: &Counts| {
+            let today = c.completed.iter().filter(|at| at.with_timezone(&tz).date_naive() == today);
+            u64::try_from(today.count()).unwrap_or(u64::MAX)
+        };
+        family(
+            &mut out,
+            &counts,
+            "pompom_pomodoros_today",
+            "gauge",
+            "Work phases completed today.",
+            completed_today,
+        );
+        family(
+            &mut out,
+            &counts,
+            "pompom_focus_seconds_total",
+            "counter",
+            "Seconds spent in work phases.",
+            |c| c.focus_secs,
+        );
+        family(
+            &mut out,
+            &counts,
+            "pompom_interruptions_total",
+            "counter",
+            "Times a work phase was paused.",
+            |c| c.interruptions,
+        );
+        family(
+            &mut out,
+            &counts,
+            "pompom_abandoned_phases_total",
+            "counter",
+            "Phases stopped before their end.",
+            |c| c.abandoned,
+        );
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// A metric with a sample per task and profile.
+fn family(
+    out: &mut String,
+    counts: &BTreeMap<(String, String), Counts>,
+    name: &str,
+    kind: &str,
+    help: &str,
+    value: impl Fn(&Counts) -> u64,
+) {
+    header(out, name, kind, help);
+    for ((task, profile), c) in counts {
+        let (task, profile) = (escape(task), escape(profile));
+        let _ = writeln!(out, "{name}{{task=\"{task}\",profile=\"{profile}\"}} {}", value(c));
+    }
+}
+
+/// `s` as a label value.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Counts the phases of the set, run with `profile`, as they end.
+pub async fn follow(metrics: Arc<Metrics>, profile: String, mut events: SetEvents) {
+    while let Some(event) = events.next().await {
+        metrics.record(&profile, &event);
+    }
+}
+
+/// Serves `GET /metrics` on `127.0.0.1` in the background, returning the
+/// address it listens on.
+pub fn serve(
+    config: &MetricsConfig,
+    remote: Arc<Remote>,
+    metrics: Arc<Metrics>,
+) -> miette::Result<SocketAddr> {
+    let make_service = make_service_fn(move |_conn| {
+        let remote = Arc::clone(&remote);
+        let metrics = Arc::clone(&metrics);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let response = handle(&req, &remote, &metrics);
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+    let addr = SocketAddr::from(([127, 0, 0, 1], config.port));
+    let server = Server::try_bind(&addr)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to serve metrics on {addr}"))?
+        .serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            log::warn!("Metrics server stopped: {e}");
+        }
+    });
+    Ok(addr)
+}
+
+fn handle(req: &Request<Body>, remote: &Remote, metrics: &Metrics) -> Response<Body> {
+    if (req.method(), req.uri().path()) != (&Method::GET, "/metrics") {
+        let mut response = Response::new(Body::from("Not found, try GET /metrics\n"));
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    }
+    let text = metrics.render(&remote.status(), schedule::local_timezone(), Utc::now());
+    let mut response = Response::new(Body::from(text));
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain; version=0.0.4"));
+    response
+}
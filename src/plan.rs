@@ -0,0 +1,246 @@
+//! `pompom plan`: a timeline of the day's pomodoros, slotted around blocked time
+//! and calendar meetings.
+
+use std::{collections::VecDeque, fmt};
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use miette::{IntoDiagnostic, WrapErr};
+use serde::{Deserialize, Serialize};
+use tabled::{Table, Tabled};
+
+use crate::{
+    calendar,
+    config::{Config, Profile},
+    db::{Database, Task},
+    schedule::{self, hhmm},
+    HumanDuration,
+};
+
+/// A recurring range of wall-clock time to plan around, e.g. lunch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockedRange {
+    #[serde(with = "hhmm")]
+    pub start: NaiveTime,
+    #[serde(with = "hhmm")]
+    pub end: NaiveTime,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default = "every_day")]
+    pub days: Vec<Weekday>,
+}
+
+fn every_day() -> Vec<Weekday> {
+    "daily".parse::<schedule::Days>().map(|days| days.0).unwrap_or_default()
+}
+
+impl BlockedRange {
+    /// Concrete busy periods on `days` consecutive dates starting at `from`.
+    pub fn occurrences(&self, tz: Tz, from: NaiveDate, days: i64) -> Vec<Busy> {
+        (0..days)
+            .map(|offset| from + Duration::days(offset))
+            .filter(|date| self.days.contains(&date.weekday()))
+            .filter_map(|date| {
+                let start = tz.from_local_datetime(&date.and_time(self.start)).earliest()?;
+                let end_date = if self.end <= self.start { date.succ_opt()? } else { date };
+                let end = tz.from_local_datetime(&end_date.and_time(self.end)).earliest()?;
+                Some(Busy {
+                    start: start.with_timezone(&Utc),
+                    end: end.with_timezone(&Utc),
+                    label: self.label.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// A period no pomodoro should overlap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Busy {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub label: Option<String>,
+}
+
+/// The first of `busy` that a phase of `length` starting at `start` would run
+/// into, and how long the phase can be to end as it starts, unless that's
+/// less than a minute.
+pub fn overlap(
+    busy: &[Busy],
+    start: DateTime<Utc>,
+    length: HumanDuration,
+) -> Option<(&Busy, Option<HumanDuration>)> {
+    let end = start + length.to_chrono();
+    let meeting = busy.iter().filter(|b| b.start < end && b.end > start).min_by_key(|b| b.start)?;
+    let left = (meeting.start - start).num_seconds();
+    let shortened = (left >= 60).then(|| HumanDuration::from_secs(left.unsigned_abs()));
+    Some((meeting, shortened))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Work,
+    ShortBreak,
+    LongBreak,
+    Blocked,
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EntryKind::Work => "Work",
+            EntryKind::ShortBreak => "Short break",
+            EntryKind::LongBreak => "Long break",
+            EntryKind::Blocked => "Blocked",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanEntry {
+    pub kind: EntryKind,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// The task slotted into a work phase, or the label of a blocked period.
+    pub note: Option<String>,
+}
+
+/// The task of each work phase in turn, each repeated for as many pomodoros
+/// as it is estimated at.
+pub fn slots(tasks: &[Task]) -> VecDeque<String> {
+    tasks
+        .iter()
+        .flat_map(|t| std::iter::repeat_n(t.title.clone(), usize::from(t.estimate)))
+        .collect()
+}
+
+/// Lays out pomodoro sets from `start` until every task has its estimated
+/// pomodoros, and at least one full set. Phases are pushed back past any
+/// overlapping `busy` period.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use pompom::{
+///     config::Profile,
+///     db::Task,
+///     plan::{build, Busy, EntryKind},
+///     HumanDuration,
+/// };
+///
+/// let profile = Profile {
+///     work: HumanDuration::from_mins(25),
+///     short_break: HumanDuration::from_mins(5),
+///     long_break: HumanDuration::from_mins(20),
+///     cycles: 2,
+/// };
+/// let start = Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+/// let standup = Busy {
+///     start: Utc.with_ymd_and_hms(2026, 10, 19, 9, 40, 0).unwrap(),
+///     end: Utc.with_ymd_and_hms(2026, 10, 19, 10, 0, 0).unwrap(),
+///     label: Some("Standup".into()),
+/// };
+/// let plan = build(&profile, &[Task::new("Report")], &[standup], start);
+/// let kinds: Vec<_> = plan.iter().map(|e| e.kind).collect();
+/// assert_eq!(
+///     kinds,
+///     [EntryKind::Work, EntryKind::ShortBreak, EntryKind::Blocked, EntryKind::Work, EntryKind::LongBreak]
+/// );
+/// assert_eq!(plan[0].note.as_deref(), Some("Report"));
+/// assert_eq!(plan[3].start, Utc.with_ymd_and_hms(2026, 10, 19, 10, 0, 0).unwrap());
+/// ```
+pub fn build(
+    profile: &Profile,
+    tasks: &[Task],
+    busy: &[Busy],
+    start: DateTime<Utc>,
+) -> Vec<PlanEntry> {
+    let mut busy = busy.to_vec();
+    busy.sort_by_key(|b| b.start);
+    let mut busy = busy.into_iter().peekable();
+    let mut slots = slots(tasks);
+    let cycles = profile.cycles.max(1);
+    let sets = slots.len().div_ceil(usize::from(cycles)).max(1);
+
+    let mut entries = Vec::new();
+    let mut t = start;
+    let mut place = |kind: EntryKind, len: Duration, note: Option<String>| {
+        while let Some(b) = busy.next_if(|b| b.start < t + len) {
+            if b.end <= t {
+                continue;
+            }
+            t = b.end;
+            entries.push(PlanEntry {
+                kind: EntryKind::Blocked,
+                start: b.start,
+                end: b.end,
+                note: b.label,
+            });
+        }
+        entries.push(PlanEntry { kind, start: t, end: t + len, note });
+        t += len;
+    };
+
+    for _ in 0..sets {
+        for cycle in 1..=cycles {
+            place(EntryKind::Work, profile.work.to_chrono(), slots.pop_front());
+            if cycle == cycles {
+                place(EntryKind::LongBreak, profile.long_break.to_chrono(), None);
+            } else {
+                place(EntryKind::ShortBreak, profile.short_break.to_chrono(), None);
+            }
+        }
+    }
+    entries
+}
+
+#[derive(Tabled)]
+struct PlanRow {
+    start: String,
+    end: String,
+    phase: String,
+    #[tabled(rename = "task")]
+    note: String,
+}
+
+/// `$ pompom plan --at 14:00`, planned from `now` unless `at` is given.
+pub fn run_command(
+    profile: &Profile,
+    config: &Config,
+    at: Option<NaiveTime>,
+    now: DateTime<Utc>,
+) -> miette::Result<()> {
+    let tz = schedule::local_timezone();
+    let today = now.with_timezone(&tz).date_naive();
+    let start = match at {
+        Some(at) => tz
+            .from_local_datetime(&today.and_time(at))
+            .earliest()
+            .map_or(now, |start| start.with_timezone(&Utc)),
+        None => now,
+    };
+
+    let tasks = Database::open_history()?
+        .pending_tasks()
+        .into_diagnostic()
+        .wrap_err("Failed to read tasks")?;
+    let busy = calendar::load_busy(config, tz, today, 2)?;
+
+    let mut cycle = 0;
+    let rows = build(profile, &tasks, &busy, start).into_iter().map(|entry| {
+        let phase = match entry.kind {
+            EntryKind::Work => {
+                cycle = cycle % profile.cycles.max(1) + 1;
+                format!("{} {}/{}", entry.kind, cycle, profile.cycles.max(1))
+            }
+            kind => kind.to_string(),
+        };
+        PlanRow {
+            start: entry.start.with_timezone(&tz).format("%H:%M").to_string(),
+            end: entry.end.with_timezone(&tz).format("%H:%M").to_string(),
+            phase,
+            note: entry.note.unwrap_or_default(),
+        }
+    });
+    println!("{}", Table::new(rows));
+    Ok(())
+}
@@ -0,0 +1,211 @@
+//! The progress bar drawn for each phase, from the `[progress]` table of the
+//! config.
+//!
+//! Templates are [`indicatif`] templates, with these keys on top of the
+//! built-in ones like `{bar}`, `{msg}` and `{elapsed}`:
+//!
+//! - `{remaining}`: time left, as `mm:ss`
+//! - `{eta}`: the local time the phase ends at, as `HH:MM`
+//! - `{phase}`: `work`, `short break` or `long break`
+//! - `{task}`: title of the task, if there's one
+//! - `{cycle}`: the work cycle out of the set, e.g. `2/4`
+//!
+//! ```toml
+//! [progress]
+//! compact = false
+//! work = "{phase:.green} {bar:40.green/white} {remaining} until {eta} {task}"
+//! short_break = "{phase:.yellow} {bar:40.yellow/white} {remaining}"
+//! ```
+//!
+//! Phases without a template use one in the color of the [`Theme`]. Plain
+//! timers and stopwatches are drawn in its work color.
+
+use std::fmt::Write;
+
+use chrono::Local;
+use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use termcolor::Color;
+
+use crate::{
+    events::{Phase, PhaseKind},
+    theme::Theme,
+    HumanDuration,
+};
+
+/// The `[progress]` table of the config.
+///
+/// ```
+/// use pompom::config::Config;
+///
+/// let config: Config = toml::from_str("[progress]\nwork = \"{bar} {remaining}\"").unwrap();
+/// assert_eq!(config.progress.work.as_deref(), Some("{bar} {remaining}"));
+/// assert!(toml::from_str::<Config>("[progress]\nwork = \"{bar:wide}\"").is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressConfig {
+    /// One short line per phase, for small tmux panes. Also `--compact`.
+    pub compact: bool,
+    #[serde(deserialize_with = "template")]
+    pub work: Option<String>,
+    #[serde(deserialize_with = "template")]
+    pub short_break: Option<String>,
+    #[serde(deserialize_with = "template")]
+    pub long_break: Option<String>,
+}
+
+impl ProgressConfig {
+    fn template(&self, kind: PhaseKind) -> Option<&str> {
+        match kind {
+            PhaseKind::Work => self.work.as_deref(),
+            PhaseKind::ShortBreak => self.short_break.as_deref(),
+            PhaseKind::LongBreak => self.long_break.as_deref(),
+        }
+    }
+}
+
+/// Draws phases the way the config and theme say.
+#[derive(Debug, Clone, Default)]
+pub struct Bars {
+    config: ProgressConfig,
+    theme: Theme,
+}
+
+impl Bars {
+    pub fn new(config: ProgressConfig, theme: Theme) -> Self {
+        Self { config, theme }
+    }
+
+    /// Whether phases are drawn on a single line, without the line that
+    /// announces them.
+    pub fn compact(&self) -> bool {
+        self.config.compact
+    }
+
+    /// A bar counting the seconds of `phase`.
+    pub fn start(&self, phase: &Phase) -> ProgressBar {
+        let color = style_color(self.theme.color(phase.kind));
+        let default = if self.compact() {
+            format!("{{phase:.{color}}} {{cycle}} {{bar:12.{color}/white}} {{remaining}}")
+        } else {
+            format!(
+                "{{bar:30.{color}/white}} {{remaining}} until {{eta}} · {{phase}} {{cycle}} \
+                 {{task}} {{wide_msg}}"
+            )
+        };
+        let template = self.config.template(phase.kind).unwrap_or(&default);
+        // Templates in the config were checked when it was read.
+        let style = ProgressStyle::with_template(template)
+            .unwrap_or_else(|_e| ProgressStyle::default_bar())
+            .progress_chars("█▓░");
+        ProgressBar::new(phase.length.as_secs()).with_style(with_keys(style, phase))
+    }
+
+    /// A bar counting down a plain timer of `length`, named by its message.
+    pub fn countdown(&self, length: HumanDuration) -> ProgressBar {
+        let color = style_color(self.theme.work);
+        let template = if self.compact() {
+            format!("{{msg:.{color}}} {{bar:12.{color}/white}} {{remaining}}")
+        } else {
+            format!("{{bar:30.{color}/white}} {{remaining}} until {{eta}} · {{wide_msg}}")
+        };
+        let style = ProgressStyle::with_template(&template)
+            .unwrap_or_else(|_e| ProgressStyle::default_bar())
+            .progress_chars("█▓░");
+        ProgressBar::new(length.as_secs()).with_style(with_time_keys(style))
+    }
+
+    /// A spinner for a stopwatch, showing its message.
+    pub fn stopwatch(&self) -> ProgressBar {
+        let color = style_color(self.theme.work);
+        let style = ProgressStyle::with_template(&format!("{{spinner:.{color}}} {{wide_msg}}"))
+            .unwrap_or_else(|_e| ProgressStyle::default_spinner());
+        ProgressBar::new_spinner().with_style(style)
+    }
+
+    /// The message a finished `phase` ends with.
+    pub fn finished(kind: PhaseKind) -> &'static str {
+        match kind {
+            PhaseKind::Work => "Pomodoro finished! Take a break!",
+            PhaseKind::ShortBreak | PhaseKind::LongBreak => "Break's over!",
+        }
+    }
+}
+
+fn with_keys(style: ProgressStyle, phase: &Phase) -> ProgressStyle {
+    let name = phase_name(phase.kind);
+    let task = phase.task.clone().unwrap_or_default();
+    let cycle = format!("{}/{}", phase.cycle, phase.cycles);
+    with_time_keys(style)
+        .with_key("phase", move |_: &ProgressState, w: &mut dyn Write| {
+            let _written = w.write_str(name);
+        })
+        .with_key("task", move |_: &ProgressState, w: &mut dyn Write| {
+            let _written = w.write_str(&task);
+        })
+        .with_key("cycle", move |_: &ProgressState, w: &mut dyn Write| {
+            let _written = w.write_str(&cycle);
+        })
+}
+
+/// The `{remaining}` and `{eta}` keys, which only need the bar.
+fn with_time_keys(style: ProgressStyle) -> ProgressStyle {
+    style
+        .with_key("remaining", |state: &ProgressState, w: &mut dyn Write| {
+            let _written = write!(w, "{}", clock_time(left(state)));
+        })
+        .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
+            let ends_at =
+                Local::now() + chrono::Duration::seconds(left(state).try_into().unwrap_or(0));
+            let _written = write!(w, "{}", ends_at.format("%H:%M"));
+        })
+}
+
+fn phase_name(kind: PhaseKind) -> &'static str {
+    match kind {
+        PhaseKind::Work => "work",
+        PhaseKind::ShortBreak => "short break",
+        PhaseKind::LongBreak => "long break",
+    }
+}
+
+/// Seconds left of the phase the bar counts.
+fn left(state: &ProgressState) -> u64 {
+    state.len().unwrap_or_default().saturating_sub(state.pos())
+}
+
+/// `secs` as `mm:ss`, or `h:mm:ss` from an hour on.
+fn clock_time(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
+
+/// `color` as a style of a template, e.g. `{bar:40.green}`. Bars can't be
+/// drawn in RGB colors, so those are left white.
+fn style_color(color: Color) -> String {
+    match color {
+        Color::Black => "black".to_string(),
+        Color::Blue => "blue".to_string(),
+        Color::Green => "green".to_string(),
+        Color::Red => "red".to_string(),
+        Color::Cyan => "cyan".to_string(),
+        Color::Magenta => "magenta".to_string(),
+        Color::Yellow => "yellow".to_string(),
+        Color::Ansi256(n) => n.to_string(),
+        _ => "white".to_string(),
+    }
+}
+
+/// Reads a template, and refuses ones [`indicatif`] can't draw.
+fn template<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let template = Option::<String>::deserialize(deserializer)?;
+    if let Some(template) = &template {
+        ProgressStyle::with_template(template).map_err(serde::de::Error::custom)?;
+    }
+    Ok(template)
+}
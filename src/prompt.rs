@@ -0,0 +1,199 @@
+//! `pompom prompt`: a short segment like `🍅 12:34` for a shell prompt or
+//! the tmux status line, and `pompom init <shell>` to set one up.
+//!
+//! A running set keeps a small state file up to date, at
+//! `$XDG_RUNTIME_DIR/pompom/state.json` or next to the history database, so
+//! the prompt can be polled every second without opening the database or
+//! the config.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db,
+    events::{Event, PhaseKind, SetEvents},
+    HumanDuration,
+};
+
+/// Segment printed when `--format` isn't given.
+pub const DEFAULT_FORMAT: &str = "{icon} {remaining}";
+
+/// Where the running set writes its [`Segment`].
+pub fn state_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("pompom").join("state.json"),
+        None => db::default_path().with_file_name("state.json"),
+    }
+}
+
+/// The phase that's running, as the state file has it.
+///
+/// ```
+/// use chrono::{Duration, Utc};
+/// use pompom::{events::PhaseKind, prompt::Segment, HumanDuration};
+///
+/// let at = Utc::now();
+/// let segment = Segment { kind: PhaseKind::Work, remaining: HumanDuration::from_mins(25), at, paused: false };
+/// assert_eq!(segment.render("{icon} {remaining}", at + Duration::seconds(90)), "🍅 23:30");
+/// assert_eq!(segment.render("{phase} {remaining}", at + Duration::hours(1)), "");
+///
+/// let segment = Segment { kind: PhaseKind::ShortBreak, remaining: HumanDuration::from_secs(190), at, paused: true };
+/// assert_eq!(segment.render("{icon} {remaining}", at + Duration::hours(1)), "⏸ 3:10");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Segment {
+    pub kind: PhaseKind,
+    /// Time left of the phase `at` the time the segment was written.
+    pub remaining: HumanDuration,
+    pub at: DateTime<Utc>,
+    pub paused: bool,
+}
+
+impl Segment {
+    /// Time left of the phase by `now`, or `None` once it should have ended,
+    /// e.g. because pompom was killed.
+    pub fn remaining_at(&self, now: DateTime<Utc>) -> Option<HumanDuration> {
+        if self.paused {
+            return Some(self.remaining);
+        }
+        let elapsed = u64::try_from((now - self.at).num_seconds()).unwrap_or(0);
+        self.remaining.as_secs().checked_sub(elapsed).map(HumanDuration::from_secs)
+    }
+
+    /// `format` with `{icon}`, `{phase}` and `{remaining}` filled in, or
+    /// nothing if the phase is over.
+    pub fn render(&self, format: &str, now: DateTime<Utc>) -> String {
+        let Some(remaining) = self.remaining_at(now) else {
+            return String::new();
+        };
+        let secs = remaining.as_secs();
+        let icon = match (self.paused, self.kind) {
+            (true, _) => "⏸",
+            (false, PhaseKind::Work) => "🍅",
+            (false, PhaseKind::ShortBreak | PhaseKind::LongBreak) => "☕",
+        };
+        format
+            .replace("{icon}", icon)
+            .replace("{phase}", self.kind.as_str())
+            .replace("{remaining}", &format!("{}:{:02}", secs / 60, secs % 60))
+    }
+
+    fn write(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Written aside and renamed over, so a poll never reads half a file.
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_vec(self)?)?;
+        fs::rename(partial, path)
+    }
+}
+
+/// Keeps the state file up to date with the phases of the set, and removes
+/// it when the set ends.
+pub async fn follow(mut events: SetEvents) {
+    let path = state_path();
+    while let Some(event) = events.next().await {
+        let segment = match event {
+            Event::PhaseStarted { phase } => Segment {
+                kind: phase.kind,
+                remaining: phase.length,
+                at: phase.started_at,
+                paused: false,
+            },
+            Event::Paused { phase, remaining } => {
+                Segment { kind: phase.kind, remaining, at: Utc::now(), paused: true }
+            }
+            Event::Resumed { phase, remaining } => {
+                Segment { kind: phase.kind, remaining, at: Utc::now(), paused: false }
+            }
+            Event::PhaseEnded { .. } | Event::Interrupted { .. } | Event::SetCompleted { .. } => {
+                match fs::remove_file(&path) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        log::warn!("Failed to remove {}: {e}", path.display());
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if let Err(e) = segment.write(&path) {
+            log::warn!("Failed to write {}: {e}", path.display());
+        }
+    }
+}
+
+/// `$ pompom prompt`, which prints nothing when no phase is running.
+pub fn run_command(format: Option<&str>) -> miette::Result<()> {
+    let Ok(state) = fs::read(state_path()) else {
+        return Ok(());
+    };
+    let Ok(segment) = serde_json::from_slice::<Segment>(&state) else {
+        return Ok(());
+    };
+    let segment = segment.render(format.unwrap_or(DEFAULT_FORMAT), Utc::now());
+    if !segment.is_empty() {
+        println!("{segment}");
+    }
+    Ok(())
+}
+
+/// Shells and terminal multiplexers `pompom init` has a snippet for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Tmux,
+}
+
+impl Shell {
+    /// Sets up the segment in the prompt, or the status line of tmux.
+    pub fn snippet(self) -> &'static str {
+        match self {
+            Shell::Bash => BASH,
+            Shell::Zsh => ZSH,
+            Shell::Fish => FISH,
+            Shell::Tmux => TMUX,
+        }
+    }
+}
+
+const BASH: &str = r#"# Add to ~/.bashrc: eval "$(pompom init bash)"
+__pompom_prompt() {
+    local segment
+    segment=$(pompom prompt 2>/dev/null) && [ -n "$segment" ] && printf '%s ' "$segment"
+}
+PS1='$(__pompom_prompt)'"$PS1"
+"#;
+
+const ZSH: &str = r#"# Add to ~/.zshrc: eval "$(pompom init zsh)"
+setopt PROMPT_SUBST
+RPROMPT='$(pompom prompt 2>/dev/null)'"$RPROMPT"
+"#;
+
+const FISH: &str = r#"# Add to ~/.config/fish/config.fish: pompom init fish | source
+if functions -q fish_right_prompt; and not functions -q __pompom_right_prompt
+    functions -c fish_right_prompt __pompom_right_prompt
+end
+function fish_right_prompt
+    pompom prompt 2>/dev/null
+    functions -q __pompom_right_prompt; and __pompom_right_prompt
+end
+"#;
+
+const TMUX: &str = r#"# Add to ~/.tmux.conf: pompom init tmux >> ~/.tmux.conf
+set -g status-interval 1
+set -ag status-right ' #(pompom prompt 2>/dev/null)'
+"#;
+
+/// `$ pompom init <shell>`
+pub fn init(shell: Shell) {
+    print!("{}", shell.snippet());
+}
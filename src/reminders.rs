@@ -0,0 +1,121 @@
+//! Reminders while a phase runs, every five minutes and when it's over,
+//! from the `[reminders]` table of the config.
+//!
+//! ```toml
+//! [reminders]
+//! style = "both" # "speech", "desktop", "both" or "off"
+//! voice = "female1"
+//! ```
+
+use std::fmt;
+
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::NotifyResult,
+    events::{Event, PhaseKind, SetEvents},
+};
+
+/// Voices `spd-say -t` knows.
+pub const VOICES: [&str; 8] =
+    ["male1", "male2", "male3", "female1", "female2", "female3", "child_male", "child_female"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReminderStyle {
+    /// Said out loud with `spd-say`.
+    #[default]
+    Speech,
+    /// Shown as desktop notifications.
+    Desktop,
+    Both,
+    Off,
+}
+
+impl ReminderStyle {
+    pub const ALL: [ReminderStyle; 4] =
+        [ReminderStyle::Speech, ReminderStyle::Desktop, ReminderStyle::Both, ReminderStyle::Off];
+
+    fn speaks(self) -> bool {
+        matches!(self, ReminderStyle::Speech | ReminderStyle::Both)
+    }
+
+    fn shows(self) -> bool {
+        matches!(self, ReminderStyle::Desktop | ReminderStyle::Both)
+    }
+}
+
+impl fmt::Display for ReminderStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReminderStyle::Speech => "spoken",
+            ReminderStyle::Desktop => "desktop notifications",
+            ReminderStyle::Both => "spoken and desktop notifications",
+            ReminderStyle::Off => "off",
+        })
+    }
+}
+
+/// The `[reminders]` table of the config.
+///
+/// ```
+/// use pompom::{config::Config, reminders::ReminderStyle};
+///
+/// let config: Config = toml::from_str("[reminders]\nstyle = \"desktop\"").unwrap();
+/// assert_eq!(config.reminders.style, ReminderStyle::Desktop);
+/// assert_eq!(config.reminders.voice, None);
+/// assert!(toml::from_str::<Config>("[reminders]\nstyle = \"loud\"").is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Reminders {
+    pub style: ReminderStyle,
+    /// Voice of `spd-say`, one of [`VOICES`]. Its default voice if unset.
+    pub voice: Option<String>,
+}
+
+impl Reminders {
+    fn remind(&self, text: &str) {
+        if self.style.speaks() {
+            if let Err(e) = crate::speak(text, self.voice.as_deref()) {
+                log::warn!("{:?}", miette::Report::new(e));
+            }
+        }
+        if self.style.shows() {
+            if let Err(e) = show(text) {
+                log::warn!("{:?}", miette::Report::new(e));
+            }
+        }
+    }
+}
+
+fn show(text: &str) -> NotifyResult {
+    Notification::new().summary("pompom").body(text).icon("alarm").appname("pompom").show()?;
+    Ok(())
+}
+
+/// Reminds of the phases of the set as they run and end.
+pub async fn follow(reminders: Reminders, mut events: SetEvents) {
+    if reminders.style == ReminderStyle::Off {
+        return;
+    }
+    while let Some(event) = events.next().await {
+        let text = match event {
+            Event::ReminderDue { elapsed, .. } => {
+                format!("{} minutes over", elapsed.as_secs() / 60)
+            }
+            Event::PhaseEnded { phase, .. } if phase.kind == PhaseKind::Work => {
+                "Work session done".to_string()
+            }
+            Event::PhaseEnded { .. } => "Break over".to_string(),
+            _ => continue,
+        };
+        let reminders = reminders.clone();
+        // Speech waits for `spd-say`, which would hold up other tasks.
+        let reminded = tokio::task::spawn_blocking(move || reminders.remind(&text));
+        if let Err(e) = reminded.await {
+            log::warn!("Failed to remind: {e}");
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Scheduled auto-start and working hours.
+//!
+//! Times are wall-clock times in each entry's own time zone, so a schedule at
+//! `09:30 Europe/Berlin` follows daylight saving changes.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use tabled::{Table, Tabled};
+
+use crate::{
+    config::Config,
+    error::{ParseError, PomodoroError},
+};
+
+/// Starts a pomodoro set at `at` on each of `days`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    #[serde(with = "hhmm")]
+    pub at: NaiveTime,
+    pub days: Vec<Weekday>,
+    pub timezone: Tz,
+}
+
+impl Schedule {
+    /// The first start strictly after `now`.
+    ///
+    /// ```
+    /// use chrono::{NaiveTime, TimeZone, Utc, Weekday};
+    /// use pompom::schedule::Schedule;
+    ///
+    /// let schedule = Schedule {
+    ///     at: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+    ///     days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+    ///     timezone: chrono_tz::Europe::Berlin,
+    /// };
+    /// // Saturday 2026-10-17, so the next start is Monday 09:30 CEST.
+    /// let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+    /// assert_eq!(
+    ///     schedule.next_after(now),
+    ///     Some(Utc.with_ymd_and_hms(2026, 10, 19, 7, 30, 0).unwrap())
+    /// );
+    /// ```
+    pub fn next_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let today = now.with_timezone(&self.timezone).date_naive();
+        (0..=7)
+            .map(|offset| today + Duration::days(offset))
+            .filter(|date| self.days.contains(&date.weekday()))
+            .filter_map(|date| {
+                self.timezone.from_local_datetime(&date.and_time(self.at)).earliest()
+            })
+            .map(|start| start.with_timezone(&Utc))
+            .find(|start| *start > now)
+    }
+}
+
+/// The earliest upcoming start across `schedules`.
+pub fn next_start(
+    schedules: &[Schedule],
+    now: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, &Schedule)> {
+    schedules
+        .iter()
+        .filter_map(|schedule| schedule.next_after(now).map(|start| (start, schedule)))
+        .min_by_key(|(start, _)| *start)
+}
+
+/// Hours during which starting a pomodoro is expected. An `end` before
+/// `start` runs past midnight into the next day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkingHours {
+    #[serde(with = "hhmm")]
+    pub start: NaiveTime,
+    #[serde(with = "hhmm")]
+    pub end: NaiveTime,
+    #[serde(default = "weekdays")]
+    pub days: Vec<Weekday>,
+    pub timezone: Tz,
+    /// Refuse to start outside working hours instead of only warning.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl WorkingHours {
+    /// Whether `now` falls in the hours, which belong to the day they
+    /// started on.
+    ///
+    /// ```
+    /// use chrono::{NaiveTime, TimeZone, Utc, Weekday};
+    /// use pompom::schedule::WorkingHours;
+    ///
+    /// let night = WorkingHours {
+    ///     start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+    ///     end: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+    ///     days: vec![Weekday::Fri],
+    ///     timezone: chrono_tz::UTC,
+    ///     strict: false,
+    /// };
+    /// // 2026-10-23 is a Friday.
+    /// let at = |day, hour| Utc.with_ymd_and_hms(2026, 10, day, hour, 0, 0).unwrap();
+    /// assert!(night.contains(at(23, 23)));
+    /// assert!(night.contains(at(24, 5)));
+    /// assert!(!night.contains(at(24, 6)));
+    /// assert!(!night.contains(at(23, 5)));
+    /// assert!(!night.contains(at(24, 23)));
+    /// ```
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        let local = now.with_timezone(&self.timezone);
+        let time = local.time();
+        if self.start <= self.end {
+            return self.days.contains(&local.weekday()) && self.start <= time && time < self.end;
+        }
+        if self.start <= time {
+            self.days.contains(&local.weekday())
+        } else {
+            time < self.end && self.days.contains(&local.weekday().pred())
+        }
+    }
+}
+
+fn weekdays() -> Vec<Weekday> {
+    Days::from_str("mon-fri").map(|days| days.0).unwrap_or_default()
+}
+
+/// Days of the week parsed from `mon-fri`, `mon,wed,fri`, `weekdays`,
+/// `weekends` or `daily`.
+///
+/// ```
+/// use chrono::Weekday;
+/// use pompom::schedule::Days;
+///
+/// let days: Days = "fri-mon".parse().unwrap();
+/// assert_eq!(days.0, vec![Weekday::Fri, Weekday::Sat, Weekday::Sun, Weekday::Mon]);
+/// assert_eq!("mon,wed".parse::<Days>().unwrap().to_string(), "Mon,Wed");
+/// assert!("someday".parse::<Days>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Days(pub Vec<Weekday>);
+
+impl FromStr for Days {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let day = |d: &str| {
+            d.trim().parse::<Weekday>().map_err(|_e| ParseError {
+                message: Some(format!("`{d}` is not a day of the week")),
+            })
+        };
+        let range = |from: Weekday, to: Weekday| {
+            let mut days = vec![from];
+            let mut d = from;
+            while d != to {
+                d = d.succ();
+                days.push(d);
+            }
+            days
+        };
+
+        let days = match s.trim().to_lowercase().as_str() {
+            "daily" => range(Weekday::Mon, Weekday::Sun),
+            "weekdays" => range(Weekday::Mon, Weekday::Fri),
+            "weekends" => range(Weekday::Sat, Weekday::Sun),
+            s => {
+                let mut days = Vec::new();
+                for part in s.split(',') {
+                    match part.split_once('-') {
+                        Some((from, to)) => days.extend(range(day(from)?, day(to)?)),
+                        None => days.push(day(part)?),
+                    }
+                }
+                days
+            }
+        };
+        Ok(Self(days))
+    }
+}
+
+impl fmt::Display for Days {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        f.write_str(&days.join(","))
+    }
+}
+
+/// Parses a wall-clock time such as `09:30`.
+pub fn parse_time(s: &str) -> Result<NaiveTime, ParseError> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .map_err(|e| ParseError { message: Some(format!("time `{s}`: {e}, expected HH:MM")) })
+}
+
+/// The system time zone, or UTC if it can't be determined.
+pub fn local_timezone() -> Tz {
+    iana_time_zone::get_timezone().ok().and_then(|name| name.parse().ok()).unwrap_or(Tz::UTC)
+}
+
+/// Serializes [`NaiveTime`] as `HH:MM`, the way it's written in the config file.
+pub(crate) mod hhmm {
+    use chrono::NaiveTime;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&time.format("%H:%M"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        super::parse_time(&s).map_err(D::Error::custom)
+    }
+}
+
+#[derive(Debug, Subcommand, PartialEq, Clone)]
+pub enum ScheduleCommands {
+    /// Usage: $ pompom schedule list
+    List,
+
+    /// Usage: $ pompom schedule add --at 09:30 --days mon-fri --timezone Europe/Berlin
+    Add {
+        /// Start time as `HH:MM`.
+        #[arg(long, value_parser = parse_time)]
+        at: NaiveTime,
+        /// e.g. `mon-fri`, `mon,wed,fri`, `weekends` or `daily`.
+        #[arg(long, default_value = "mon-fri")]
+        days: Days,
+        /// IANA time zone name. Defaults to the system time zone.
+        #[arg(long)]
+        timezone: Option<Tz>,
+    },
+
+    /// Usage: $ pompom schedule rm 1
+    Rm {
+        /// Number of the schedule as shown by `pompom schedule list`.
+        index: usize,
+    },
+}
+
+#[derive(Tabled)]
+struct ScheduleRow {
+    #[tabled(rename = "#")]
+    index: usize,
+    at: String,
+    days: Days,
+    timezone: Tz,
+    #[tabled(rename = "next start")]
+    next: String,
+}
+
+pub fn run_command(command: &ScheduleCommands) -> miette::Result<()> {
+    let mut config = Config::load()?;
+    match command {
+        ScheduleCommands::List => {
+            if config.schedules.is_empty() {
+                println!("No schedules. Add one with `pompom schedule add --at 09:30`.");
+                return Ok(());
+            }
+            let now = Utc::now();
+            let rows = config.schedules.iter().enumerate().map(|(i, s)| ScheduleRow {
+                index: i + 1,
+                at: s.at.format("%H:%M").to_string(),
+                days: Days(s.days.clone()),
+                timezone: s.timezone,
+                next: s.next_after(now).map_or_else(String::new, |start| {
+                    start.with_timezone(&s.timezone).format("%a %Y-%m-%d %H:%M %Z").to_string()
+                }),
+            });
+            println!("{}", Table::new(rows));
+        }
+        ScheduleCommands::Add { at, days, timezone } => {
+            let schedule = Schedule {
+                at: *at,
+                days: days.0.clone(),
+                timezone: timezone.unwrap_or_else(local_timezone),
+            };
+            let timezone = schedule.timezone;
+            config.schedules.push(schedule);
+            config.save_schedules()?;
+            println!(
+                "Added schedule #{}: {} on {days} ({timezone})",
+                config.schedules.len(),
+                at.format("%H:%M"),
+            );
+        }
+        ScheduleCommands::Rm { index } => {
+            if *index == 0 || *index > config.schedules.len() {
+                return Err(PomodoroError::NoSchedule(*index).into());
+            }
+            config.schedules.remove(index - 1);
+            config.save_schedules()?;
+            println!("Removed schedule #{index}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,476 @@
+//! Pomodoro sets without a user interface, for programs that embed the
+//! timer: configure a [`Timer`], [`Timer::start`] a set, then poll or
+//! subscribe to its [`Session`] and pause or stop it.
+//!
+//! ```
+//! use pompom::{
+//!     config::Profile,
+//!     events::Event,
+//!     session::{Outcome, Timer},
+//!     HumanDuration,
+//! };
+//!
+//! # tokio::runtime::Runtime::new().unwrap().block_on(async {
+//! let profile = Profile { work: HumanDuration::from_mins(25), cycles: 4, ..Profile::default() };
+//! let timer = Timer::new(profile).with_task(Some("Write report".into()));
+//! let mut events = timer.subscribe();
+//! let session = timer.start();
+//!
+//! let Ok(Event::PhaseStarted { phase }) = events.recv().await else { panic!() };
+//! assert_eq!(phase.task.as_deref(), Some("Write report"));
+//! assert_eq!(session.status().state, "work");
+//! session.pause().unwrap();
+//! session.stop().unwrap();
+//! assert_eq!(session.wait().await, Outcome::Stopped);
+//! # });
+//! ```
+//!
+//! Timing goes by a [`Clock`], which can be a
+//! [`ManualClock`](crate::clock::ManualClock) so tests don't wait.
+
+use std::{collections::VecDeque, fmt, future::Future, ops::ControlFlow, pin::Pin, sync::Arc};
+
+use tokio::{
+    sync::{broadcast, mpsc, Mutex, OwnedMutexGuard},
+    task::JoinHandle,
+};
+
+use crate::{
+    api::{Command, Refusal, Remote, Status},
+    clock::{Clock, SystemClock},
+    config::Profile,
+    db::{Database, Task},
+    events::{Event, EventBus, Phase, PhaseKind, SetEvents},
+    plan::{self, Busy},
+    HumanDuration, PomofocusState, StateManager,
+};
+
+/// How often [`Event::ReminderDue`] is sent during a phase.
+const REMINDER_INTERVAL: HumanDuration = HumanDuration::from_mins(5);
+
+/// An answer a [`Prompter`] gives once it has it.
+pub type Ask<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// What a set asks the person running it, if there's one. The default
+/// answers ask no one.
+pub trait Prompter: fmt::Debug + Send + Sync {
+    /// Told that a work phase of `work` would run into `meeting`. Returns
+    /// whether to cut it to `shortened` so it ends as the meeting starts,
+    /// which isn't offered if that would leave less than a minute.
+    fn overlaps(
+        &self,
+        work: HumanDuration,
+        meeting: &Busy,
+        shortened: Option<HumanDuration>,
+    ) -> Ask<bool> {
+        Box::pin(std::future::ready(false))
+    }
+
+    /// A note on what got done in the work phase that just ended.
+    fn reflect(&self, phase: &Phase) -> Ask<Option<String>> {
+        Box::pin(std::future::ready(None))
+    }
+}
+
+/// Keeps work phases as long as configured and takes no notes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unattended;
+
+impl Prompter for Unattended {}
+
+/// Where the task of each work phase comes from.
+#[derive(Debug, Clone, Default)]
+enum Tasks {
+    #[default]
+    None,
+    Fixed(String),
+    /// The tasks given, or else those pending in the history database when
+    /// the set starts, slotted in as [`plan::build`] does.
+    Queue(Option<Vec<Task>>),
+}
+
+impl Tasks {
+    /// The task of each work phase of a set in turn, for a queue.
+    fn slots(&self) -> VecDeque<String> {
+        match self {
+            Tasks::None | Tasks::Fixed(_) => VecDeque::new(),
+            Tasks::Queue(Some(tasks)) => plan::slots(tasks),
+            Tasks::Queue(None) => {
+                match Database::open_default().and_then(|db| db.pending_tasks()) {
+                    Ok(tasks) => plan::slots(&tasks),
+                    Err(e) => {
+                        log::warn!("Failed to read the task queue: {e}");
+                        VecDeque::new()
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// How a set ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Completed,
+    /// Stopped through [`Session::stop`], the HTTP API or D-Bus.
+    Stopped,
+    /// Stopped through [`Session::cancel`], as `Ctrl+c` does.
+    Cancelled,
+}
+
+/// Runs pomodoro sets, one at a time. Clones share their events, status and
+/// commands.
+///
+/// * Builder Lite pattern: Call site
+/// ```
+/// use std::sync::Arc;
+/// use pompom::{clock::SystemClock, config::Profile, session::{Timer, Unattended}};
+///
+/// let timer = Timer::new(Profile::default())
+///     .with_clock(Arc::new(SystemClock)) // default
+///     .with_prompter(Arc::new(Unattended)) // default
+///     .with_task_queue();
+/// assert_eq!(timer.status().state, "idle");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Timer {
+    profile: Profile,
+    clock: Arc<dyn Clock>,
+    prompter: Arc<dyn Prompter>,
+    /// Meetings and blocked time that work phases are fitted around.
+    busy: Vec<Busy>,
+    tasks: Tasks,
+    events: Arc<EventBus>,
+    /// Status and transitions shared with the HTTP API and D-Bus.
+    remote: Arc<Remote>,
+    /// Commands sent through the remote, received by the running set.
+    commands: Arc<Mutex<mpsc::UnboundedReceiver<Command>>>,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new(Profile::default())
+    }
+}
+
+impl Timer {
+    pub fn new(profile: Profile) -> Self {
+        let (remote, commands) = Remote::new();
+        Self {
+            profile,
+            clock: Arc::new(SystemClock),
+            prompter: Arc::new(Unattended),
+            busy: Vec::new(),
+            tasks: Tasks::None,
+            events: Arc::default(),
+            remote,
+            commands: Arc::new(Mutex::new(commands)),
+        }
+    }
+
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_prompter(mut self, prompter: Arc<dyn Prompter>) -> Self {
+        self.prompter = prompter;
+        self
+    }
+
+    pub fn with_busy(mut self, busy: Vec<Busy>) -> Self {
+        self.busy = busy;
+        self
+    }
+
+    /// Spends every work phase on the task titled `task`.
+    pub fn with_task(mut self, task: Option<String>) -> Self {
+        self.tasks = task.map_or(Tasks::None, Tasks::Fixed);
+        self
+    }
+
+    /// Spends work phases on the tasks queued with `pompom task add`, in
+    /// order, each for as many completed pomodoros as it is estimated at.
+    pub fn with_task_queue(mut self) -> Self {
+        self.tasks = Tasks::Queue(None);
+        self
+    }
+
+    /// Like [`Timer::with_task_queue`], with `tasks` in place of the queue.
+    pub fn with_tasks(mut self, tasks: Vec<Task>) -> Self {
+        self.tasks = Tasks::Queue(Some(tasks));
+        self
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    pub fn clock(&self) -> &dyn Clock {
+        &*self.clock
+    }
+
+    /// Every event from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// The events from now on until the current or next set ends.
+    pub fn subscribe_set(&self) -> SetEvents {
+        self.events.subscribe_set()
+    }
+
+    /// Status and controls, for serving them, e.g. over the HTTP API.
+    pub fn remote(&self) -> Arc<Remote> {
+        Arc::clone(&self.remote)
+    }
+
+    pub fn status(&self) -> Status {
+        self.remote.status()
+    }
+
+    /// Waits for a command sent through the remote while no set runs, e.g.
+    /// `Start` from the HTTP API. `None` once no one can send one.
+    pub async fn next_command(&self) -> Option<Command> {
+        self.commands.lock().await.recv().await
+    }
+
+    /// Starts a set in the background, after the one running if any.
+    pub fn start(&self) -> Session {
+        let timer = self.clone();
+        let run = tokio::spawn(async move {
+            let commands = Arc::clone(&timer.commands).lock_owned().await;
+            let state_manager =
+                StateManager::new(PomofocusState::None).with_max_count(Some(timer.profile.cycles));
+            let slots = timer.tasks.slots();
+            Run { timer, commands, state_manager, slots }.run_set().await
+        });
+        Session { remote: Arc::clone(&self.remote), events: Arc::clone(&self.events), run }
+    }
+}
+
+/// A set started by [`Timer::start`].
+#[derive(Debug)]
+pub struct Session {
+    remote: Arc<Remote>,
+    events: Arc<EventBus>,
+    run: JoinHandle<Outcome>,
+}
+
+impl Session {
+    pub fn status(&self) -> Status {
+        self.remote.status()
+    }
+
+    /// Every event from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    pub fn pause(&self) -> Result<Status, Refusal> {
+        self.remote.send(Command::Pause)
+    }
+
+    pub fn resume(&self) -> Result<Status, Refusal> {
+        self.remote.send(Command::Resume)
+    }
+
+    /// Ends the current phase now and goes on with the next.
+    pub fn skip(&self) -> Result<Status, Refusal> {
+        self.remote.send(Command::Skip)
+    }
+
+    /// Ends the set.
+    pub fn stop(&self) -> Result<Status, Refusal> {
+        self.remote.send(Command::Stop)
+    }
+
+    /// Ends the set like `Ctrl+c`, see [`Outcome::Cancelled`].
+    pub fn cancel(&self) -> Result<Status, Refusal> {
+        self.remote.send(Command::Cancel)
+    }
+
+    /// Waits for the set to end.
+    pub async fn wait(self) -> Outcome {
+        match self.run.await {
+            Ok(outcome) => outcome,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(_) => Outcome::Stopped,
+        }
+    }
+}
+
+/// A set being run.
+struct Run {
+    timer: Timer,
+    commands: OwnedMutexGuard<mpsc::UnboundedReceiver<Command>>,
+    state_manager: StateManager,
+    /// What's left of the queue, see [`Tasks::Queue`].
+    slots: VecDeque<String>,
+}
+
+impl Run {
+    async fn run_set(&mut self) -> Outcome {
+        let profile = self.timer.profile.clone();
+        while self.state_manager.counter.unwrap_or(0) < profile.cycles {
+            self.state_manager.set_next_state();
+            match self.state_manager.get_state() {
+                PomofocusState::Work => {
+                    let work = self.fit_work_phase().await;
+                    let phase = self.phase(PhaseKind::Work, self.next_task(), work);
+                    match self.prog(&phase).await {
+                        ControlFlow::Break(outcome) => return outcome,
+                        // A skipped pomodoro doesn't count towards the estimate.
+                        ControlFlow::Continue(completed) => {
+                            if completed {
+                                self.slots.pop_front();
+                            }
+                        }
+                    }
+                    if let Some(note) = self.timer.prompter.reflect(&phase).await {
+                        self.publish(Event::Reflected { phase, note });
+                    }
+                    self.state_manager.next_counter();
+                }
+                PomofocusState::ShortBreak => {
+                    let phase = self.phase(PhaseKind::ShortBreak, None, profile.short_break);
+                    if let ControlFlow::Break(outcome) = self.prog(&phase).await {
+                        return outcome;
+                    }
+                }
+                PomofocusState::LongBreak | PomofocusState::None => {}
+            }
+            if self.state_manager.counter.unwrap_or(0) == profile.cycles {
+                self.state_manager.state = PomofocusState::LongBreak;
+                let phase = self.phase(PhaseKind::LongBreak, None, profile.long_break);
+                if let ControlFlow::Break(outcome) = self.prog(&phase).await {
+                    return outcome;
+                }
+                let at = self.timer.clock.now();
+                self.publish(Event::SetCompleted { cycles: profile.cycles, at });
+                self.state_manager.set_next_state();
+            }
+        }
+        Outcome::Completed
+    }
+
+    /// Updates the status, then tells the subscribers.
+    fn publish(&self, event: Event) {
+        self.timer.remote.apply(&event);
+        self.timer.events.publish(event);
+    }
+
+    fn next_task(&self) -> Option<String> {
+        match &self.timer.tasks {
+            Tasks::None => None,
+            Tasks::Fixed(title) => Some(title.clone()),
+            Tasks::Queue(_) => self.slots.front().cloned(),
+        }
+    }
+
+    /// The length of a work phase starting now, cut to end as a meeting
+    /// starts if the prompter says so.
+    async fn fit_work_phase(&self) -> HumanDuration {
+        let work = self.timer.profile.work;
+        let Some((meeting, shortened)) =
+            plan::overlap(&self.timer.busy, self.timer.clock.now(), work)
+        else {
+            return work;
+        };
+        let shorten = self.timer.prompter.overlaps(work, meeting, shortened).await;
+        shortened.filter(|_| shorten).unwrap_or(work)
+    }
+
+    /// A phase of `kind` starting now, placed in the set.
+    fn phase(&self, kind: PhaseKind, task: Option<String>, length: HumanDuration) -> Phase {
+        // A break comes after its work phase has been counted.
+        let counter = self.state_manager.counter.unwrap_or(0);
+        let cycle = if kind == PhaseKind::Work { counter + 1 } else { counter.max(1) };
+        let cycles = self.timer.profile.cycles;
+        Phase { kind, task, cycle, cycles, length, started_at: self.timer.clock.now() }
+    }
+
+    /// Counts `phase` down, publishing its events, while the remote can
+    /// pause, skip or stop it. Breaks with the outcome if it ended the set,
+    /// or else continues with whether it ran to its end.
+    async fn prog(&mut self, phase: &Phase) -> ControlFlow<Outcome, bool> {
+        // Breaks of no length are left out, e.g. for a single countdown.
+        if phase.kind != PhaseKind::Work && phase.length.as_secs() == 0 {
+            return ControlFlow::Continue(true);
+        }
+        let second = chrono::Duration::seconds(1);
+        // Ticks are due whole seconds after the start, so a late one doesn't push
+        // back the rest.
+        let mut next_tick = phase.started_at + second;
+        self.publish(Event::PhaseStarted { phase: phase.clone() });
+        let length = phase.length.as_secs();
+        let mut left = length;
+        let mut paused = false;
+        let mut pauses = 0;
+        let mut stopped = None;
+        while left > 0 {
+            let clock = Arc::clone(&self.timer.clock);
+            tokio::select! {
+                _ = clock.sleep_until(next_tick), if !paused => {
+                    next_tick += second;
+                    left -= 1;
+                    let remaining = HumanDuration::from_secs(left);
+                    self.publish(Event::Tick { kind: phase.kind, remaining });
+                    let elapsed = length - left;
+                    if left > 0 && elapsed.is_multiple_of(REMINDER_INTERVAL.as_secs()) {
+                        let elapsed = HumanDuration::from_secs(elapsed);
+                        self.publish(Event::ReminderDue { phase: phase.clone(), elapsed });
+                    }
+                }
+                Some(command) = self.commands.recv() => match command {
+                    Command::Pause if !paused => {
+                        paused = true;
+                        pauses += 1;
+                        let remaining = HumanDuration::from_secs(left);
+                        self.publish(Event::Paused { phase: phase.clone(), remaining });
+                    }
+                    Command::Resume if paused => {
+                        paused = false;
+                        next_tick = self.timer.clock.now() + second;
+                        let remaining = HumanDuration::from_secs(left);
+                        self.publish(Event::Resumed { phase: phase.clone(), remaining });
+                    }
+                    Command::Skip => break,
+                    Command::Stop => {
+                        stopped = Some(Outcome::Stopped);
+                        break;
+                    }
+                    Command::Cancel => {
+                        stopped = Some(Outcome::Cancelled);
+                        break;
+                    }
+                    Command::Start | Command::Pause | Command::Resume => {}
+                },
+            }
+        }
+        let ended_at = self.timer.clock.now();
+        let elapsed = HumanDuration::from_secs(length - left);
+        let phase = phase.clone();
+        if let Some(outcome) = stopped {
+            let remaining = HumanDuration::from_secs(left);
+            let cancelled = outcome == Outcome::Cancelled;
+            self.publish(Event::Interrupted {
+                phase,
+                ended_at,
+                elapsed,
+                remaining,
+                pauses,
+                cancelled,
+            });
+            return ControlFlow::Break(outcome);
+        }
+        let skipped = left > 0;
+        self.publish(Event::PhaseEnded { phase, ended_at, elapsed, pauses, skipped });
+        ControlFlow::Continue(!skipped)
+    }
+}
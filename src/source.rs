@@ -0,0 +1,486 @@
+//! Tasks kept outside pompom, in `todo.txt` or Org files.
+//!
+//! Sources are configured by path and referred to as `@todo:3` or `@org:2`:
+//!
+//! ```toml
+//! [sources]
+//! todo = "~/todo/todo.txt"
+//! org = "~/org/work.org"
+//! ```
+//!
+//! After each work phase spent on a source task, the source is updated: a
+//! `pomo:N` counter in `todo.txt`, a `CLOCK:` line under the Org headline.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use miette::{IntoDiagnostic, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    calendar::expand_home,
+    db::Database,
+    error::{ParseError, PomodoroError},
+};
+
+/// Paths of the configured task sources.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskSources {
+    pub todo: Option<PathBuf>,
+    pub org: Option<PathBuf>,
+}
+
+impl TaskSources {
+    /// Adapters for every configured source.
+    pub fn all(&self) -> Vec<Box<dyn TaskSource>> {
+        let mut sources: Vec<Box<dyn TaskSource>> = Vec::new();
+        if let Some(path) = &self.todo {
+            sources.push(Box::new(TodoTxt { path: expand_home(path) }));
+        }
+        if let Some(path) = &self.org {
+            sources.push(Box::new(Org { path: expand_home(path) }));
+        }
+        sources
+    }
+
+    fn get(&self, name: &str) -> miette::Result<Box<dyn TaskSource>> {
+        let source = self.all().into_iter().find(|source| source.name() == name);
+        Ok(source.ok_or_else(|| PomodoroError::UnknownSource(name.to_string()))?)
+    }
+}
+
+/// An open item in a task source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceItem {
+    /// Line number in `todo.txt`, position among open headlines in Org.
+    pub id: usize,
+    pub title: String,
+    /// Pomodoros already recorded against the item.
+    pub pomodoros: u32,
+}
+
+/// A list of tasks that lives in a file of its own.
+pub trait TaskSource {
+    /// Used in task references, e.g. `todo` in `@todo:3`.
+    fn name(&self) -> &'static str;
+
+    fn path(&self) -> &Path;
+
+    /// Items still to be done, parsed from the file's contents.
+    fn parse(&self, contents: &str) -> Vec<SourceItem>;
+
+    /// The file's contents after a pomodoro on `item`.
+    fn record(
+        &self,
+        contents: &str,
+        item: &SourceItem,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+    ) -> String;
+
+    fn open_items(&self) -> miette::Result<Vec<SourceItem>> {
+        let path = self.path();
+        let contents = std::fs::read_to_string(path).into_diagnostic().wrap_err_with(|| {
+            format!("Failed to read {} tasks from {}", self.name(), path.display())
+        })?;
+        Ok(self.parse(&contents))
+    }
+}
+
+/// `todo.txt`, where open tasks are lines not starting with `x `.
+#[derive(Debug, Clone)]
+pub struct TodoTxt {
+    pub path: PathBuf,
+}
+
+impl TaskSource for TodoTxt {
+    fn name(&self) -> &'static str {
+        "todo"
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// ```
+    /// use pompom::source::{TaskSource, TodoTxt};
+    ///
+    /// let todo = TodoTxt { path: "todo.txt".into() };
+    /// let items = todo.parse("(A) Call Mom +family pomo:2\nx 2026-10-18 Done already\nWrite report\n");
+    /// assert_eq!(items.len(), 2);
+    /// assert_eq!((items[0].id, items[0].title.as_str(), items[0].pomodoros), (1, "(A) Call Mom +family", 2));
+    /// assert_eq!(items[1].id, 3);
+    /// ```
+    fn parse(&self, contents: &str) -> Vec<SourceItem> {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with("x "))
+            .map(|(i, line)| {
+                let (title, pomodoros) = split_pomo(line);
+                SourceItem { id: i + 1, title, pomodoros }
+            })
+            .collect()
+    }
+
+    /// ```
+    /// use chrono::Utc;
+    /// use pompom::source::{TaskSource, TodoTxt};
+    ///
+    /// let todo = TodoTxt { path: "todo.txt".into() };
+    /// let contents = "Call Mom pomo:2 +family\nWrite report\n";
+    /// let items = todo.parse(contents);
+    /// let now = Utc::now();
+    /// let contents = todo.record(contents, &items[0], now, now);
+    /// let contents = todo.record(&contents, &items[1], now, now);
+    /// assert_eq!(contents, "Call Mom pomo:3 +family\nWrite report pomo:1\n");
+    /// ```
+    fn record(
+        &self,
+        contents: &str,
+        item: &SourceItem,
+        _started_at: DateTime<Utc>,
+        _ended_at: DateTime<Utc>,
+    ) -> String {
+        let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+        let Some(index) = find_line(&lines, item.id - 1, |line| split_pomo(line).0 == item.title)
+        else {
+            log::warn!("Task `{}` is no longer in {}", item.title, self.path.display());
+            return contents.to_string();
+        };
+        let line = &lines[index];
+        let count = split_pomo(line).1 + 1;
+        lines[index] = match line.split(' ').position(|word| word.starts_with("pomo:")) {
+            Some(pos) => {
+                let mut words: Vec<&str> = line.split(' ').collect();
+                let counter = format!("pomo:{count}");
+                words[pos] = &counter;
+                words.join(" ")
+            }
+            None => format!("{} pomo:{count}", line.trim_end()),
+        };
+        join_lines(&lines, contents)
+    }
+}
+
+/// Splits the `pomo:N` counter off a todo.txt line.
+fn split_pomo(line: &str) -> (String, u32) {
+    let mut count = 0;
+    let words: Vec<&str> = line
+        .split_whitespace()
+        .filter(|word| match word.strip_prefix("pomo:").map(str::parse::<u32>) {
+            Some(Ok(n)) => {
+                count = n;
+                false
+            }
+            _ => true,
+        })
+        .collect();
+    (words.join(" "), count)
+}
+
+/// Org files, where open tasks are headlines with a TODO keyword. Keywords
+/// come from `#+TODO:` lines, defaulting to `TODO` and `NEXT`.
+#[derive(Debug, Clone)]
+pub struct Org {
+    pub path: PathBuf,
+}
+
+/// A headline with an open keyword, at line `line`.
+struct Headline {
+    line: usize,
+    title: String,
+}
+
+impl Org {
+    fn headlines(contents: &str) -> Vec<Headline> {
+        let mut keywords: Vec<String> = contents
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                line.strip_prefix("#+TODO:")
+                    .or_else(|| line.strip_prefix("#+SEQ_TODO:"))
+                    .or_else(|| line.strip_prefix("#+TYP_TODO:"))
+            })
+            .flat_map(|spec| {
+                // Keywords after `|` mark done states, or only the last one
+                // without a `|`. `TODO(t)` has a fast-access key.
+                let mut open: Vec<String> = spec
+                    .split('|')
+                    .next()
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(|k| k.split('(').next().unwrap_or(k).to_string())
+                    .collect();
+                if !spec.contains('|') {
+                    open.pop();
+                }
+                open
+            })
+            .collect();
+        if keywords.is_empty() {
+            keywords = vec![String::from("TODO"), String::from("NEXT")];
+        }
+
+        contents
+            .lines()
+            .enumerate()
+            .filter_map(|(line, text)| {
+                let rest = text.strip_prefix('*')?.trim_start_matches('*');
+                let rest = rest.strip_prefix(' ')?.trim_start();
+                let (keyword, title) = rest.split_once(' ').unwrap_or((rest, ""));
+                keywords
+                    .iter()
+                    .any(|k| k == keyword)
+                    .then(|| Headline { line, title: clean_org_title(title) })
+            })
+            .collect()
+    }
+}
+
+/// Drops the priority cookie and trailing tags of a headline title.
+fn clean_org_title(title: &str) -> String {
+    let mut title = title.trim();
+    if let Some(rest) = title.strip_prefix("[#").and_then(|rest| rest.get(2..)) {
+        title = rest.trim_start();
+    }
+    if let Some((head, tags)) = title.rsplit_once(char::is_whitespace) {
+        if tags.len() > 1 && tags.starts_with(':') && tags.ends_with(':') {
+            title = head.trim_end();
+        }
+    }
+    title.to_string()
+}
+
+impl TaskSource for Org {
+    fn name(&self) -> &'static str {
+        "org"
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// ```
+    /// use pompom::source::{Org, TaskSource};
+    ///
+    /// let org = Org { path: "work.org".into() };
+    /// let contents = "#+TODO: TODO WAIT | DONE\n\
+    ///                 * TODO [#A] Write report :work:\n\
+    ///                 :LOGBOOK:\n\
+    ///                 CLOCK: [2026-10-19 Mon 09:00]--[2026-10-19 Mon 09:25] =>  0:25\n\
+    ///                 :END:\n\
+    ///                 * DONE Old thing\n\
+    ///                 ** WAIT Reply to Sam\n";
+    /// let items = org.parse(contents);
+    /// assert_eq!(items.len(), 2);
+    /// assert_eq!((items[0].title.as_str(), items[0].pomodoros), ("Write report", 1));
+    /// assert_eq!((items[1].id, items[1].title.as_str()), (2, "Reply to Sam"));
+    /// ```
+    fn parse(&self, contents: &str) -> Vec<SourceItem> {
+        let lines: Vec<&str> = contents.lines().collect();
+        Self::headlines(contents)
+            .into_iter()
+            .enumerate()
+            .map(|(i, headline)| {
+                let clocks = lines[headline.line + 1..]
+                    .iter()
+                    .take_while(|line| !line.starts_with('*'))
+                    .filter(|line| line.trim_start().starts_with("CLOCK:"))
+                    .count();
+                SourceItem {
+                    id: i + 1,
+                    title: headline.title,
+                    pomodoros: u32::try_from(clocks).unwrap_or(u32::MAX),
+                }
+            })
+            .collect()
+    }
+
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use pompom::source::{Org, TaskSource};
+    ///
+    /// let org = Org { path: "work.org".into() };
+    /// let contents = "* TODO Write report\nSCHEDULED: <2026-10-19 Mon>\nNotes\n* TODO Other\n";
+    /// let items = org.parse(contents);
+    /// let start = Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+    /// let end = Utc.with_ymd_and_hms(2026, 10, 19, 9, 25, 0).unwrap();
+    /// let contents = org.record(contents, &items[0], start, end);
+    /// assert!(contents.starts_with("* TODO Write report\nSCHEDULED: <2026-10-19 Mon>\n:LOGBOOK:\nCLOCK: ["));
+    /// assert!(contents.contains("] =>  0:25\n:END:\nNotes\n* TODO Other\n"));
+    /// assert_eq!(org.parse(&contents)[0].pomodoros, 1);
+    /// ```
+    fn record(
+        &self,
+        contents: &str,
+        item: &SourceItem,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+    ) -> String {
+        let headlines = Self::headlines(contents);
+        let found = headlines
+            .get(item.id - 1)
+            .filter(|h| h.title == item.title)
+            .or_else(|| headlines.iter().find(|h| h.title == item.title));
+        let Some(headline) = found else {
+            log::warn!("Headline `{}` is no longer in {}", item.title, self.path.display());
+            return contents.to_string();
+        };
+
+        let tz = crate::schedule::local_timezone();
+        let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+        let clock = clock_line(started_at, ended_at, tz);
+
+        // The logbook goes after the planning line and property drawer.
+        let mut at = headline.line + 1;
+        let is = |at: usize, prefix: &str| {
+            lines.get(at).is_some_and(|line: &String| line.trim_start().starts_with(prefix))
+        };
+        if ["SCHEDULED:", "DEADLINE:", "CLOSED:"].iter().any(|p| is(at, p)) {
+            at += 1;
+        }
+        if is(at, ":PROPERTIES:") {
+            while at < lines.len() && !lines[at].trim().eq_ignore_ascii_case(":END:") {
+                at += 1;
+            }
+            at += 1;
+        }
+        if is(at, ":LOGBOOK:") {
+            let indent: String = lines[at].chars().take_while(|c| c.is_whitespace()).collect();
+            lines.insert(at + 1, format!("{indent}{clock}"));
+        } else {
+            lines.splice(at..at, [String::from(":LOGBOOK:"), clock, String::from(":END:")]);
+        }
+        join_lines(&lines, contents)
+    }
+}
+
+/// `CLOCK: [2026-10-19 Mon 09:00]--[2026-10-19 Mon 09:25] =>  0:25` in `tz`.
+fn clock_line(started_at: DateTime<Utc>, ended_at: DateTime<Utc>, tz: Tz) -> String {
+    let stamp = |t: DateTime<Utc>| t.with_timezone(&tz).format("[%Y-%m-%d %a %H:%M]").to_string();
+    let mins = (ended_at - started_at).num_minutes().max(0);
+    format!(
+        "CLOCK: {}--{} => {:>2}:{:02}",
+        stamp(started_at),
+        stamp(ended_at),
+        mins / 60,
+        mins % 60
+    )
+}
+
+/// The line at `expected` if it matches, else the first line that does.
+fn find_line(lines: &[String], expected: usize, matches: impl Fn(&str) -> bool) -> Option<usize> {
+    if lines.get(expected).is_some_and(|line| matches(line)) {
+        return Some(expected);
+    }
+    lines.iter().position(|line| matches(line))
+}
+
+/// Joins `lines`, keeping the trailing newline of `original` if it had one.
+fn join_lines(lines: &[String], original: &str) -> String {
+    let mut out = lines.join("\n");
+    if original.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// A task to spend work phases on: `3` for a queued task, `@todo:3` or
+/// `@org:2` for a task in a source.
+///
+/// ```
+/// use pompom::source::TaskRef;
+///
+/// assert_eq!("3".parse::<TaskRef>().unwrap(), TaskRef::Local(3));
+/// let todo: TaskRef = "@todo:3".parse().unwrap();
+/// assert_eq!(todo, TaskRef::Source { source: "todo".into(), id: 3 });
+/// assert_eq!(todo.to_string(), "@todo:3");
+/// assert!("@todo".parse::<TaskRef>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskRef {
+    Local(i64),
+    Source { source: String, id: usize },
+}
+
+impl FromStr for TaskRef {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseError {
+            message: Some(format!("task `{s}`, expected a task number or `@source:number`")),
+        };
+        match s.strip_prefix('@') {
+            Some(rest) => {
+                let (source, id) = rest.split_once(':').ok_or_else(err)?;
+                let id = id.parse().map_err(|_e| err())?;
+                if id == 0 {
+                    return Err(err());
+                }
+                Ok(Self::Source { source: source.to_string(), id })
+            }
+            None => s.parse().map(Self::Local).map_err(|_e| err()),
+        }
+    }
+}
+
+impl fmt::Display for TaskRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskRef::Local(id) => write!(f, "{id}"),
+            TaskRef::Source { source, id } => write!(f, "@{source}:{id}"),
+        }
+    }
+}
+
+/// The title of the task `task` refers to.
+pub fn resolve(sources: &TaskSources, task: &TaskRef) -> miette::Result<String> {
+    match task {
+        TaskRef::Local(id) => Database::open_history()?
+            .task(*id)
+            .into_diagnostic()
+            .wrap_err("Failed to read tasks")?
+            .map(|task| task.title)
+            .ok_or_else(|| PomodoroError::NoTask(*id).into()),
+        TaskRef::Source { source, id } => Ok(source_item(sources, source, *id)?.title),
+    }
+}
+
+fn source_item(sources: &TaskSources, name: &str, id: usize) -> miette::Result<SourceItem> {
+    let source = sources.get(name)?;
+    let item = source.open_items()?.into_iter().find(|item| item.id == id);
+    Ok(item.ok_or_else(|| PomodoroError::NoSourceItem {
+        source_name: name.to_string(),
+        id,
+        path: source.path().to_path_buf(),
+    })?)
+}
+
+/// Writes a finished work phase back to the source `task` lives in. `title`
+/// is the one [`resolve`] returned, to find the task again if the file
+/// changed in the meantime.
+pub fn record_pomodoro(
+    sources: &TaskSources,
+    task: &TaskRef,
+    title: &str,
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+) -> miette::Result<()> {
+    let TaskRef::Source { source: name, id } = task else {
+        return Ok(());
+    };
+    let source = sources.get(name)?;
+    let item = SourceItem { id: *id, title: title.to_string(), pomodoros: 0 };
+    let path = source.path();
+    let contents = std::fs::read_to_string(path).into_diagnostic()?;
+    std::fs::write(path, source.record(&contents, &item, started_at, ended_at))
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to update {}", path.display()))
+}
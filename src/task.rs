@@ -0,0 +1,105 @@
+//! The queue of tasks that pomodoros are planned against.
+
+use clap::Subcommand;
+use miette::IntoDiagnostic;
+use tabled::{Table, Tabled};
+
+use crate::{
+    config::Config,
+    db::{Database, Task},
+    error::PomodoroError,
+};
+
+#[derive(Debug, Subcommand, PartialEq, Clone)]
+pub enum TaskCommands {
+    /// Usage: $ pompom task add "Write report" --estimate 2
+    Add {
+        title: String,
+        /// Number of pomodoros the task is expected to take.
+        #[arg(short, long, default_value_t = 1)]
+        estimate: u16,
+    },
+
+    /// Usage: $ pompom task list
+    ///
+    /// Also lists open tasks of the `todo.txt` and Org sources in the config.
+    List {
+        /// Include tasks already done.
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// Usage: $ pompom task done 3
+    Done { id: i64 },
+
+    /// Usage: $ pompom task rm 3
+    Rm { id: i64 },
+}
+
+#[derive(Tabled)]
+struct TaskRow {
+    id: i64,
+    title: String,
+    #[tabled(rename = "pomodoros")]
+    estimate: u16,
+    done: bool,
+}
+
+#[derive(Tabled)]
+struct SourceRow {
+    #[tabled(rename = "task")]
+    reference: String,
+    title: String,
+    pomodoros: u32,
+}
+
+pub fn run_command(command: &TaskCommands) -> miette::Result<()> {
+    let db = Database::open_history()?;
+    match command {
+        TaskCommands::Add { title, estimate } => {
+            let id = db
+                .insert_task(&Task::new(title.as_str()).with_estimate(*estimate))
+                .into_diagnostic()?;
+            println!("Added task #{id}: {title}");
+        }
+        TaskCommands::List { all } => {
+            let tasks = if *all { db.tasks() } else { db.pending_tasks() }.into_diagnostic()?;
+            let sources = Config::load()?.sources.all();
+            if tasks.is_empty() && sources.is_empty() {
+                println!("No tasks. Add one with `pompom task add \"Write report\"`.");
+                return Ok(());
+            }
+            if !tasks.is_empty() {
+                let rows = tasks.into_iter().map(|t| TaskRow {
+                    id: t.id.unwrap_or_default(),
+                    title: t.title,
+                    estimate: t.estimate,
+                    done: t.done,
+                });
+                println!("{}", Table::new(rows));
+            }
+            for source in sources {
+                println!("{}:", source.path().display());
+                let rows = source.open_items()?.into_iter().map(|item| SourceRow {
+                    reference: format!("@{}:{}", source.name(), item.id),
+                    title: item.title,
+                    pomodoros: item.pomodoros,
+                });
+                println!("{}", Table::new(rows));
+            }
+        }
+        TaskCommands::Done { id } => {
+            if !db.set_task_done(*id, true).into_diagnostic()? {
+                return Err(PomodoroError::NoTask(*id).into());
+            }
+            println!("Marked task #{id} as done");
+        }
+        TaskCommands::Rm { id } => {
+            if !db.delete_task(*id).into_diagnostic()? {
+                return Err(PomodoroError::NoTask(*id).into());
+            }
+            println!("Removed task #{id}");
+        }
+    }
+    Ok(())
+}
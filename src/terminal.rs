@@ -0,0 +1,200 @@
+//! The terminal front end of a set: a colored line and a progress bar per
+//! phase, and the prompts of a [`Prompter`].
+
+use std::sync::Arc;
+
+use dialoguer::{theme::ColorfulTheme, Confirm, Input};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+
+use crate::{
+    events::{Event, Phase, PhaseKind, SetEvents},
+    plan::Busy,
+    printer::CliPrinter,
+    progress::Bars,
+    schedule,
+    session::{Ask, Prompter},
+    HumanDuration,
+};
+
+/// A prompt waiting for its turn at the terminal.
+#[derive(Debug)]
+enum Request {
+    Shorten {
+        work: HumanDuration,
+        meeting: Busy,
+        shortened: Option<HumanDuration>,
+        answer: oneshot::Sender<bool>,
+    },
+    Reflect {
+        task: Option<String>,
+        answer: oneshot::Sender<Option<String>>,
+    },
+}
+
+/// Asks at the terminal, once what the set published before is drawn.
+#[derive(Debug)]
+pub struct TerminalPrompter {
+    requests: mpsc::UnboundedSender<Request>,
+    /// Ask for a reflection note after each work phase.
+    reflect: bool,
+}
+
+impl Prompter for TerminalPrompter {
+    fn overlaps(
+        &self,
+        work: HumanDuration,
+        meeting: &Busy,
+        shortened: Option<HumanDuration>,
+    ) -> Ask<bool> {
+        let (answer, answered) = oneshot::channel();
+        let meeting = meeting.clone();
+        let _sent = self.requests.send(Request::Shorten { work, meeting, shortened, answer });
+        Box::pin(async move { answered.await.unwrap_or(false) })
+    }
+
+    fn reflect(&self, phase: &Phase) -> Ask<Option<String>> {
+        if !self.reflect {
+            return Box::pin(std::future::ready(None));
+        }
+        let (answer, answered) = oneshot::channel();
+        let _sent = self.requests.send(Request::Reflect { task: phase.task.clone(), answer });
+        Box::pin(async move { answered.await.ok().flatten() })
+    }
+}
+
+/// Draws `events` until the set ends, and asks the prompts of the returned
+/// prompter in between.
+pub fn spawn(
+    events: SetEvents,
+    reflect: bool,
+    printer: CliPrinter,
+    bars: Bars,
+) -> (Arc<TerminalPrompter>, JoinHandle<()>) {
+    let (requests, received) = mpsc::unbounded_channel();
+    let drawing = tokio::spawn(draw(events, received, Screen { printer, bars, bar: None }));
+    (Arc::new(TerminalPrompter { requests, reflect }), drawing)
+}
+
+async fn draw(
+    mut events: SetEvents,
+    mut requests: mpsc::UnboundedReceiver<Request>,
+    mut screen: Screen,
+) {
+    loop {
+        tokio::select! {
+            event = events.next() => match event {
+                Some(event) => screen.draw(event),
+                None => return,
+            },
+            Some(request) = requests.recv() => {
+                // Catch up first, so the prompt doesn't end up in the middle of
+                // the bar.
+                while let Some(event) = events.try_next() {
+                    screen.draw(event);
+                }
+                ask(request).await;
+            }
+        }
+    }
+}
+
+/// What the set is drawn with.
+struct Screen {
+    printer: CliPrinter,
+    bars: Bars,
+    /// Bar of the phase that's running.
+    bar: Option<indicatif::ProgressBar>,
+}
+
+impl Screen {
+    fn draw(&mut self, event: Event) {
+        match event {
+            Event::PhaseStarted { phase } => {
+                if !self.bars.compact() {
+                    if let Err(e) = self.printer.phase(phase.kind) {
+                        log::warn!("Failed to print phase: {e}");
+                    }
+                }
+                self.bar = Some(self.bars.start(&phase));
+            }
+            Event::Tick { remaining, .. } => {
+                if let Some(bar) = &self.bar {
+                    let length = bar.length().unwrap_or_default();
+                    bar.set_position(length.saturating_sub(remaining.as_secs()));
+                }
+            }
+            Event::PhaseEnded { phase, .. } => {
+                if let Some(bar) = self.bar.take() {
+                    bar.finish_with_message(Bars::finished(phase.kind));
+                }
+            }
+            Event::Interrupted { phase, cancelled, .. } => {
+                if let Some(bar) = self.bar.take() {
+                    let what = if phase.kind == PhaseKind::Work { "Pomodoro" } else { "Break" };
+                    let how = if cancelled { "cancelled" } else { "stopped" };
+                    bar.abandon_with_message(format!("{what} {how}"));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn ask(request: Request) {
+    let asked = tokio::task::spawn_blocking(move || match request {
+        Request::Shorten { work, meeting, shortened, answer } => {
+            let _answered = answer.send(ask_shorten(work, &meeting, shortened));
+        }
+        Request::Reflect { task, answer } => {
+            let _answered = answer.send(ask_reflection(task.as_deref()));
+        }
+    });
+    if let Err(e) = asked.await {
+        log::warn!("Prompt failed: {e}");
+    }
+}
+
+/// Warns that a work phase of `work` runs into `meeting`, and offers to
+/// shorten it to `shortened` when someone is at the terminal to answer.
+pub(crate) fn ask_shorten(
+    work: HumanDuration,
+    meeting: &Busy,
+    shortened: Option<HumanDuration>,
+) -> bool {
+    let label = meeting.label.as_deref().unwrap_or("a meeting");
+    let at = meeting.start.with_timezone(&schedule::local_timezone()).format("%H:%M");
+    eprintln!("Warning: this {work} work phase overlaps {label} at {at}");
+
+    let Some(shortened) = shortened else {
+        return false;
+    };
+    if !dialoguer::console::user_attended() {
+        return false;
+    }
+    Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Shorten it to {shortened} so it ends before {label}?"))
+        .default(true)
+        .interact()
+        .unwrap_or(false)
+}
+
+/// Asks what got done, when someone is at the terminal to answer.
+fn ask_reflection(task: Option<&str>) -> Option<String> {
+    if !dialoguer::console::user_attended() {
+        return None;
+    }
+    let prompt = task.map_or_else(
+        || String::from("What did you get done?"),
+        |task| format!("What did you get done on {task}?"),
+    );
+    let note: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .allow_empty(true)
+        .interact_text()
+        .unwrap_or_default();
+    let note = note.trim();
+    (!note.is_empty()).then(|| note.to_string())
+}
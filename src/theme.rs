@@ -0,0 +1,161 @@
+//! How phases look in the terminal, from the `[theme]` table of the config,
+//! and when to color at all.
+//!
+//! ```toml
+//! [theme]
+//! glyphs = "ascii" # "emoji", "ascii" or "nerd" for a Nerd Font
+//! work = "green"
+//! short_break = "yellow"
+//! long_break = "208" # a color of the 256-color palette, or "r,g,b"
+//! ```
+//!
+//! Colors are left out with `--color never`, when `NO_COLOR` is set, or when
+//! stdout isn't a terminal, unless `--color always` says otherwise.
+
+use std::io::IsTerminal;
+
+use serde::{Deserialize, Serialize};
+use termcolor::{Color, ColorChoice};
+
+use crate::events::PhaseKind;
+
+/// The markers phases start with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Glyphs {
+    #[default]
+    Emoji,
+    /// Plain text, for terminals and fonts without emoji.
+    Ascii,
+    /// Icons of a patched Nerd Font.
+    Nerd,
+}
+
+impl Glyphs {
+    pub fn marker(self, kind: PhaseKind) -> &'static str {
+        match (self, kind) {
+            (Glyphs::Emoji, PhaseKind::Work) => "💪🏻",
+            (Glyphs::Emoji, PhaseKind::ShortBreak) => "💤🏻",
+            (Glyphs::Emoji, PhaseKind::LongBreak) => "💤🏻💤🏻💤🏻",
+            (Glyphs::Ascii, PhaseKind::Work) => "[work]",
+            (Glyphs::Ascii, PhaseKind::ShortBreak) => "[short break]",
+            (Glyphs::Ascii, PhaseKind::LongBreak) => "[long break]",
+            // nf-fa-briefcase, nf-fa-coffee and nf-fa-bed.
+            (Glyphs::Nerd, PhaseKind::Work) => "\u{f0b1}",
+            (Glyphs::Nerd, PhaseKind::ShortBreak) => "\u{f0f4}",
+            (Glyphs::Nerd, PhaseKind::LongBreak) => "\u{f236}",
+        }
+    }
+}
+
+/// The `[theme]` table of the config.
+///
+/// ```
+/// use pompom::{config::Config, events::PhaseKind, theme::Glyphs};
+/// use termcolor::Color;
+///
+/// let config: Config = toml::from_str("[theme]\nglyphs = \"ascii\"\nlong_break = \"208\"").unwrap();
+/// assert_eq!(config.theme.glyphs.marker(PhaseKind::Work), "[work]");
+/// assert_eq!(config.theme.color(PhaseKind::Work), Color::Green);
+/// assert_eq!(config.theme.color(PhaseKind::LongBreak), Color::Ansi256(208));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub glyphs: Glyphs,
+    #[serde(with = "color")]
+    pub work: Color,
+    #[serde(with = "color")]
+    pub short_break: Color,
+    #[serde(with = "color")]
+    pub long_break: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            glyphs: Glyphs::default(),
+            work: Color::Green,
+            short_break: Color::Yellow,
+            long_break: Color::Red,
+        }
+    }
+}
+
+impl Theme {
+    pub fn color(&self, kind: PhaseKind) -> Color {
+        match kind {
+            PhaseKind::Work => self.work,
+            PhaseKind::ShortBreak => self.short_break,
+            PhaseKind::LongBreak => self.long_break,
+        }
+    }
+}
+
+/// When to color output, set with `--color`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ColorMode {
+    /// Unless `NO_COLOR` is set, stdout isn't a terminal or `TERM=dumb`.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                let dumb = std::env::var_os("TERM").is_some_and(|term| term == "dumb");
+                !no_color && !dumb && std::io::stdout().is_terminal()
+            }
+        }
+    }
+
+    pub fn choice(self) -> ColorChoice {
+        if self.enabled() {
+            ColorChoice::Always
+        } else {
+            ColorChoice::Never
+        }
+    }
+
+    /// Colors prompts and bars the same way.
+    pub fn apply(self) {
+        let enabled = self.enabled();
+        dialoguer::console::set_colors_enabled(enabled);
+        dialoguer::console::set_colors_enabled_stderr(enabled);
+    }
+}
+
+/// Serializes a [`Color`] the way [`Color`]'s `FromStr` reads it, e.g.
+/// `green`, `208` or `255,128,0`.
+mod color {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use termcolor::Color;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = match color {
+            Color::Black => "black".to_string(),
+            Color::Blue => "blue".to_string(),
+            Color::Green => "green".to_string(),
+            Color::Red => "red".to_string(),
+            Color::Cyan => "cyan".to_string(),
+            Color::Magenta => "magenta".to_string(),
+            Color::Yellow => "yellow".to_string(),
+            Color::Ansi256(n) => n.to_string(),
+            Color::Rgb(r, g, b) => format!("{r},{g},{b}"),
+            // `White`, and any color termcolor adds later.
+            _ => "white".to_string(),
+        };
+        serializer.serialize_str(&name)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
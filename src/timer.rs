@@ -0,0 +1,141 @@
+//! Plain countdown timers and stopwatches.
+//!
+//! These share the pomodoro's progress bar, desktop notification and speech
+//! plumbing, but run a single phase with no cycles.
+
+use chrono::{DateTime, Utc};
+use miette::{IntoDiagnostic, WrapErr};
+
+use crate::{
+    clock::Clock,
+    db::{Database, Session, SessionKind, SessionOutcome},
+    notify_desktop, notify_elapsed_time,
+    progress::Bars,
+    HumanDuration, NotificationManager,
+};
+
+/// Speak the elapsed time every 5 minutes.
+const SPEAK_EVERY_SECS: u64 = 5 * 60;
+
+/// `$ pompom timer 10m tea`
+pub async fn countdown(
+    duration: HumanDuration,
+    label: Option<&str>,
+    log: bool,
+    bars: &Bars,
+    clock: &dyn Clock,
+) -> miette::Result<()> {
+    let name = label.unwrap_or("Timer");
+    let created_at = clock.now();
+
+    let pb = bars.countdown(duration);
+    pb.set_message(name.to_string());
+    for i in 1..=duration.as_secs() {
+        tokio::select! {
+            () = clock.sleep_until(created_at + HumanDuration::from_secs(i).to_chrono()) => {}
+            _ = tokio::signal::ctrl_c() => {
+                pb.abandon_with_message(format!("{name} cancelled"));
+                if log {
+                    let outcome = SessionOutcome::Interrupted;
+                    record(SessionKind::Timer, label, created_at, clock.now(), outcome)?;
+                }
+                return Ok(());
+            }
+        }
+        pb.inc(1);
+        if i.is_multiple_of(SPEAK_EVERY_SECS) && i != duration.as_secs() {
+            notify_elapsed_time(Some(format!("{} minutes over", i / 60)));
+        }
+    }
+    pb.finish_with_message(format!("{name} finished!"));
+    let ended_at = clock.now();
+
+    notify_elapsed_time(Some(format!("{name} done")));
+    notify(name, format!("{duration} elapsed"), duration, created_at, ended_at);
+    if log {
+        record(SessionKind::Timer, label, created_at, ended_at, SessionOutcome::Completed)?;
+    }
+
+    Ok(())
+}
+
+/// `$ pompom stopwatch`, stopped with `Ctrl+c`.
+pub async fn stopwatch(
+    label: Option<&str>,
+    log: bool,
+    bars: &Bars,
+    clock: &dyn Clock,
+) -> miette::Result<()> {
+    let name = label.unwrap_or("Stopwatch");
+    let created_at = clock.now();
+
+    let pb = bars.stopwatch();
+    let mut elapsed = 0;
+    loop {
+        pb.set_message(format!("{name}: {}", HumanDuration::from_secs(elapsed)));
+        let next = created_at + HumanDuration::from_secs(elapsed + 1).to_chrono();
+        tokio::select! {
+            () = clock.sleep_until(next) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        elapsed += 1;
+        pb.tick();
+        if elapsed.is_multiple_of(SPEAK_EVERY_SECS) {
+            notify_elapsed_time(Some(format!("{} minutes over", elapsed / 60)));
+        }
+    }
+    let ended_at = clock.now();
+    let duration = HumanDuration::from_secs(elapsed);
+    pb.finish_with_message(format!("{name} stopped at {duration}"));
+
+    notify(name, format!("Stopped at {duration}"), duration, created_at, ended_at);
+    if log {
+        // Stopping is how a stopwatch ends, so it counts as completed.
+        record(SessionKind::Stopwatch, label, created_at, ended_at, SessionOutcome::Completed)?;
+    }
+
+    Ok(())
+}
+
+fn notify(
+    name: &str,
+    body: String,
+    duration: HumanDuration,
+    created_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+) {
+    let args = NotificationManager {
+        id: None,
+        description: name.to_string(),
+        work_time: duration,
+        short_break_time: HumanDuration::default(),
+        long_break_time: HumanDuration::default(),
+        created_at,
+        work_expired_at: Some(ended_at),
+        break_expired_at: None,
+        body,
+        icon: "alarm",
+        timeout: 2000,
+        appname: "pompom",
+    };
+    if let Err(e) = notify_desktop(args) {
+        log::warn!("Failed to show desktop notification: {e}");
+    }
+}
+
+fn record(
+    kind: SessionKind,
+    label: Option<&str>,
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+    outcome: SessionOutcome,
+) -> miette::Result<()> {
+    let session = Session::new(kind, started_at, ended_at)
+        .with_label(label.map(String::from))
+        .with_outcome(outcome);
+    Database::open_history()?
+        .insert_session(&session)
+        .into_diagnostic()
+        .wrap_err("Failed to log timer to history")?;
+    Ok(())
+}
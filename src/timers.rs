@@ -0,0 +1,333 @@
+//! Named timers that `pompom daemon` runs side by side, e.g. a "standup"
+//! countdown next to the scheduled pomodoro set, reached through the HTTP
+//! API.
+//!
+//! ```terminal
+//! $ pompom timer 10m standup --detach
+//! $ pompom start --detach deep-work
+//! $ pompom list
+//! $ pompom status standup
+//! $ pompom stop standup
+//! ```
+//!
+//! The sets the daemon starts on its schedule are the timer [`MAIN`]. Each
+//! timer runs its own set, and shows its desktop notifications in place of
+//! its previous one rather than stacking them.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use miette::{IntoDiagnostic, WrapErr};
+use reqwest::{Client, Method, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tabled::{Table, Tabled};
+
+use crate::{
+    api::{Refusal, Remote, Status},
+    config::{Config, Profile},
+    error::PomodoroError,
+    events::{Event, PhaseKind, SetEvents},
+    notify_desktop,
+    session::Timer,
+    HumanDuration, NotificationManager,
+};
+
+/// Name of the timer of the scheduled sets, and of the set run in the
+/// terminal.
+pub const MAIN: &str = "pomodoro";
+
+/// A timer and its status, as `GET /timers` lists it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Named {
+    pub name: String,
+    #[serde(flatten)]
+    pub status: Status,
+}
+
+/// A timer to start, as `POST /timers/<name>` takes it.
+///
+/// ```
+/// use pompom::{timers::NewTimer, HumanDuration};
+///
+/// let timer: NewTimer = serde_json::from_str(r#"{"work": "50m", "cycles": 2}"#).unwrap();
+/// assert_eq!(timer.profile.work, HumanDuration::from_mins(50));
+/// assert_eq!(timer.profile.short_break, HumanDuration::from_mins(5));
+///
+/// let timer = NewTimer::countdown(HumanDuration::from_mins(10), Some("standup".into()));
+/// assert_eq!(timer.profile.cycles, 1);
+/// assert_eq!(timer.profile.long_break, HumanDuration::from_secs(0));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewTimer {
+    #[serde(flatten)]
+    pub profile: Profile,
+    /// Title of the task of every work phase.
+    #[serde(default)]
+    pub task: Option<String>,
+}
+
+impl NewTimer {
+    /// A single phase of `duration`, with no breaks.
+    pub fn countdown(duration: HumanDuration, label: Option<String>) -> Self {
+        let none = HumanDuration::from_secs(0);
+        let profile = Profile { work: duration, short_break: none, long_break: none, cycles: 1 };
+        Self { profile, task: label }
+    }
+}
+
+/// Whether `name` can name a timer, so it fits in a URL path as it is.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The timers of a daemon: [`MAIN`], and the ones started by name, each
+/// with its own status and commands.
+#[derive(Debug)]
+pub struct Timers {
+    main: Arc<Remote>,
+    named: Mutex<BTreeMap<String, Arc<Remote>>>,
+}
+
+impl Timers {
+    pub fn new(main: Arc<Remote>) -> Arc<Self> {
+        Arc::new(Self { main, named: Mutex::default() })
+    }
+
+    /// The remote of [`MAIN`].
+    pub fn main(&self) -> Arc<Remote> {
+        Arc::clone(&self.main)
+    }
+
+    /// The remote of the timer `name`, if it runs.
+    pub fn remote(&self, name: &str) -> Option<Arc<Remote>> {
+        if name == MAIN {
+            return Some(self.main());
+        }
+        self.lock().get(name).cloned()
+    }
+
+    /// [`MAIN`] first, then the others by name.
+    pub fn list(&self) -> Vec<Named> {
+        let main = Named { name: MAIN.to_string(), status: self.main.status() };
+        let named = self.lock();
+        let named = named
+            .iter()
+            .map(|(name, remote)| Named { name: name.clone(), status: remote.status() });
+        std::iter::once(main).chain(named).collect()
+    }
+
+    /// Starts `new` as the timer `name` in the background, returning it once
+    /// its first phase has started. It's forgotten when its set ends.
+    pub async fn start(self: &Arc<Self>, name: &str, new: NewTimer) -> Result<Named, Refusal> {
+        if new.profile.cycles == 0 {
+            return Err(Refusal::Conflict("A timer needs at least one cycle"));
+        }
+        let timer = Timer::new(new.profile).with_task(new.task);
+        {
+            let mut named = self.lock();
+            if name == MAIN || named.contains_key(name) {
+                return Err(Refusal::Conflict("A timer of that name is running"));
+            }
+            named.insert(name.to_string(), timer.remote());
+        }
+        tokio::spawn(notify(name.to_string(), timer.subscribe_set()));
+        let mut events = timer.subscribe();
+        let session = timer.start();
+        let timers = Arc::clone(self);
+        let forgotten = name.to_string();
+        tokio::spawn(async move {
+            session.wait().await;
+            timers.lock().remove(&forgotten);
+        });
+        // The set publishes its first phase before anything else.
+        let _started = events.recv().await;
+        Ok(Named { name: name.to_string(), status: timer.status() })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Arc<Remote>>> {
+        self.named.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Shows a desktop notification titled `name` as each phase ends, each in
+/// place of the one before.
+async fn notify(name: String, mut events: SetEvents) {
+    let mut shown = None;
+    while let Some(event) = events.next().await {
+        let Event::PhaseEnded { phase, ended_at, .. } = event else {
+            continue;
+        };
+        let body = match phase.kind {
+            PhaseKind::Work => format!("{} elapsed", phase.length),
+            PhaseKind::ShortBreak | PhaseKind::LongBreak => "Break's over".to_string(),
+        };
+        let notified = notify_desktop(NotificationManager {
+            id: shown,
+            description: name.clone(),
+            work_time: phase.length,
+            short_break_time: HumanDuration::default(),
+            long_break_time: HumanDuration::default(),
+            created_at: phase.started_at,
+            work_expired_at: Some(ended_at),
+            break_expired_at: None,
+            body,
+            icon: "alarm",
+            timeout: 2000,
+            appname: "pompom",
+        });
+        match notified {
+            Ok(id) => shown = id,
+            Err(e) => log::warn!("{:?}", miette::Report::new(e)),
+        }
+    }
+}
+
+/// `pompom daemon`, as reached through the HTTP API of the config.
+struct Daemon {
+    client: Client,
+    url: String,
+    token: String,
+}
+
+/// The body of an answer that isn't a success.
+#[derive(Deserialize)]
+struct Failure {
+    error: String,
+}
+
+impl Daemon {
+    fn new(config: &Config) -> miette::Result<Self> {
+        let api = config.api.as_ref().ok_or(PomodoroError::NoApi)?;
+        if api.token.is_empty() {
+            return Err(PomodoroError::NoApiToken.into());
+        }
+        let client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .into_diagnostic()
+            .wrap_err("Failed to set up the HTTP client")?;
+        let url = format!("http://127.0.0.1:{}", api.port);
+        Ok(Self { client, url, token: api.token.clone() })
+    }
+
+    /// Sends a request to `path`, which is about the timer `name` if any.
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        name: Option<&str>,
+        body: Option<&NewTimer>,
+    ) -> miette::Result<T> {
+        let mut request =
+            self.client.request(method, format!("{}{path}", self.url)).bearer_auth(&self.token);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request.send().await.map_err(|e| {
+            log::debug!("Failed to reach {}: {e}", self.url);
+            PomodoroError::NoTimerRunning
+        })?;
+        let status = response.status();
+        if status.is_success() {
+            return response.json().await.into_diagnostic();
+        }
+        if let (StatusCode::NOT_FOUND, Some(name)) = (status, name) {
+            return Err(PomodoroError::NoNamedTimer(name.to_string()).into());
+        }
+        let reason = response.json::<Failure>().await.map(|f| f.error).unwrap_or_default();
+        Err(miette::miette!("pompom daemon answered {}: {}", status, reason))
+    }
+}
+
+#[derive(Tabled)]
+struct TimerRow {
+    name: String,
+    state: String,
+    cycle: String,
+    remaining: String,
+    task: String,
+}
+
+/// `$ pompom list`
+pub async fn list_command(config: &Config) -> miette::Result<()> {
+    let timers: Vec<Named> = Daemon::new(config)?.send(Method::GET, "/timers", None, None).await?;
+    let rows = timers.into_iter().map(|Named { name, status }| TimerRow {
+        state: state(&status),
+        cycle: if status.cycles > 0 {
+            format!("{}/{}", status.cycle, status.cycles)
+        } else {
+            String::new()
+        },
+        remaining: remaining(&status),
+        task: status.task.unwrap_or_default(),
+        name,
+    });
+    println!("{}", Table::new(rows));
+    Ok(())
+}
+
+/// `$ pompom status <name>`
+pub async fn status_command(config: &Config, name: &str) -> miette::Result<()> {
+    let path = format!("/timers/{}", checked(name)?);
+    let Named { name, status } =
+        Daemon::new(config)?.send(Method::GET, &path, Some(name), None).await?;
+    if status.state == "idle" {
+        println!("{name}: idle");
+        return Ok(());
+    }
+    let task = status.task.as_ref().map(|task| format!(" · {task}")).unwrap_or_default();
+    println!(
+        "{name}: {} {}/{}, {} left{task}",
+        state(&status),
+        status.cycle,
+        status.cycles,
+        remaining(&status)
+    );
+    Ok(())
+}
+
+/// `$ pompom stop <name>`
+pub async fn stop_command(config: &Config, name: &str) -> miette::Result<()> {
+    let path = format!("/timers/{}/stop", checked(name)?);
+    let _status: Status = Daemon::new(config)?.send(Method::POST, &path, Some(name), None).await?;
+    println!("Stopped {name}");
+    Ok(())
+}
+
+/// `$ pompom timer 10m standup --detach` and `$ pompom start --detach
+/// <name>`, which hand `new` to the daemon as the timer `name`.
+pub async fn detach(config: &Config, name: &str, new: NewTimer) -> miette::Result<()> {
+    let path = format!("/timers/{}", checked(name)?);
+    let Named { status, .. } =
+        Daemon::new(config)?.send(Method::POST, &path, None, Some(&new)).await?;
+    println!("Started {name} in pompom daemon, {} left", remaining(&status));
+    Ok(())
+}
+
+fn checked(name: &str) -> miette::Result<&str> {
+    if valid_name(name) {
+        Ok(name)
+    } else {
+        Err(PomodoroError::TimerName(name.to_string()).into())
+    }
+}
+
+fn state(status: &Status) -> String {
+    let state = status.state.replace('_', " ");
+    if status.paused {
+        format!("{state} (paused)")
+    } else {
+        state
+    }
+}
+
+/// Time left of the phase as `m:ss`, empty while idle.
+fn remaining(status: &Status) -> String {
+    if status.state == "idle" {
+        return String::new();
+    }
+    format!("{}:{:02}", status.remaining / 60, status.remaining % 60)
+}
@@ -0,0 +1,283 @@
+//! File-based interchange with Taskwarrior and Timewarrior.
+//!
+//! Tasks come in from the JSON written by `task export`. Completed work phases
+//! go out as interval lines in Timewarrior's monthly data files.
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::{
+    db::{Session, SessionKind, SessionOutcome, Task},
+    error::ParseError,
+};
+
+/// The fields of a `task export` entry that pompom uses.
+#[derive(Debug, Deserialize)]
+struct TaskwarriorTask {
+    uuid: String,
+    description: String,
+    status: String,
+    entry: Option<String>,
+    project: Option<String>,
+}
+
+/// Pending tasks of a `task export`, keeping their Taskwarrior UUIDs so
+/// importing the same export again doesn't duplicate them.
+///
+/// ```
+/// use pompom::warrior::parse_taskwarrior;
+///
+/// let json = r#"[
+///   {"uuid": "a7f0c1de-5b0e-4a43-9c36-0d5a2b6f1e01", "description": "Write report",
+///    "status": "pending", "entry": "20261019T070000Z", "project": "work"},
+///   {"uuid": "1d2c3b4a-0000-4000-8000-000000000002", "description": "Old",
+///    "status": "completed", "entry": "20261001T070000Z"}
+/// ]"#;
+/// let tasks = parse_taskwarrior(json).unwrap();
+/// assert_eq!(tasks.len(), 1);
+/// assert_eq!(tasks[0].title, "work: Write report");
+/// assert_eq!(tasks[0].uuid, "a7f0c1de-5b0e-4a43-9c36-0d5a2b6f1e01");
+/// ```
+pub fn parse_taskwarrior(json: &str) -> Result<Vec<Task>, ParseError> {
+    let tasks: Vec<TaskwarriorTask> = serde_json::from_str(json)
+        .map_err(|e| ParseError { message: Some(format!("Taskwarrior export: {e}")) })?;
+    Ok(tasks
+        .into_iter()
+        .filter(|t| t.status == "pending")
+        .map(|t| {
+            let title = match &t.project {
+                Some(project) => format!("{project}: {}", t.description),
+                None => t.description,
+            };
+            let mut task = Task::new(title);
+            task.uuid = t.uuid;
+            if let Some(entry) = t.entry.as_deref().and_then(parse_timestamp) {
+                task.created_at = entry;
+            }
+            task
+        })
+        .collect())
+}
+
+/// Whether `json` looks like a `task export`, which is a bare array.
+pub fn is_taskwarrior(json: &str) -> bool {
+    json.trim_start().starts_with('[')
+}
+
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.strip_suffix('Z')?;
+    NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S").ok().map(|dt| Utc.from_utc_datetime(&dt))
+}
+
+fn format_timestamp(t: DateTime<Utc>) -> String {
+    t.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// A Timewarrior interval line for a work phase, tagged `pompom` plus its task
+/// and label, with the reflection note as annotation.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use pompom::db::{Session, SessionKind};
+/// use pompom::warrior::interval_line;
+///
+/// let start = Utc.with_ymd_and_hms(2026, 10, 19, 7, 0, 0).unwrap();
+/// let session = Session::new(SessionKind::Work, start, start + chrono::Duration::minutes(25))
+///     .with_task(Some("Write report".into()));
+/// assert_eq!(
+///     interval_line(&session),
+///     r#"inc 20261019T070000Z - 20261019T072500Z # pompom "Write report""#
+/// );
+/// ```
+pub fn interval_line(session: &Session) -> String {
+    let mut line = format!(
+        "inc {} - {} # pompom",
+        format_timestamp(session.started_at),
+        format_timestamp(session.ended_at)
+    );
+    for tag in [&session.task, &session.label].into_iter().flatten() {
+        line.push(' ');
+        line.push_str(&quote_tag(tag));
+    }
+    if let Some(note) = &session.note {
+        line.push_str(" # ");
+        line.push_str(&serde_json::Value::from(note.as_str()).to_string());
+    }
+    line
+}
+
+fn quote_tag(tag: &str) -> String {
+    if tag.contains([' ', '"', '#']) {
+        format!("\"{}\"", tag.replace('"', "\\\""))
+    } else {
+        tag.to_string()
+    }
+}
+
+/// Timewarrior's data directory: `$TIMEWARRIORDB/data`, `~/.timewarrior/data`
+/// if it exists, else `$XDG_DATA_HOME/timewarrior/data`.
+pub fn timewarrior_data_dir() -> PathBuf {
+    if let Some(db) = std::env::var_os("TIMEWARRIORDB") {
+        return PathBuf::from(db).join("data");
+    }
+    let home = PathBuf::from(std::env::var_os("HOME").unwrap_or_default());
+    let legacy = home.join(".timewarrior");
+    if legacy.is_dir() {
+        return legacy.join("data");
+    }
+    std::env::var_os("XDG_DATA_HOME")
+        .map_or_else(|| home.join(".local/share"), PathBuf::from)
+        .join("timewarrior")
+        .join("data")
+}
+
+/// Interval lines for completed work phases missing from the monthly data
+/// files in `dir`, grouped by file. Intervals already there with the same
+/// start and end are left alone.
+pub fn pending_intervals(
+    dir: &Path,
+    sessions: &[Session],
+) -> std::io::Result<BTreeMap<PathBuf, Vec<String>>> {
+    let mut files: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    let mut existing: BTreeMap<PathBuf, String> = BTreeMap::new();
+    let completed = sessions
+        .iter()
+        .filter(|s| s.kind == SessionKind::Work && s.outcome == SessionOutcome::Completed);
+    for session in completed {
+        let path = dir.join(format!("{}.data", session.started_at.format("%Y-%m")));
+        if !existing.contains_key(&path) {
+            let contents = match std::fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e),
+            };
+            existing.insert(path.clone(), contents);
+        }
+        let line = interval_line(session);
+        let span = line.split(" #").next().unwrap_or_default().to_string();
+        let present = existing.get(&path).is_some_and(|contents| {
+            contents.lines().any(|l| l.split(" #").next() == Some(span.as_str()))
+        });
+        let queued = files.get(&path).is_some_and(|lines| lines.contains(&line));
+        if !present && !queued {
+            files.entry(path).or_default().push(line);
+        }
+    }
+    Ok(files)
+}
+
+/// Adds the output of [`pending_intervals`] to its data files, keeping
+/// each file in chronological order, and counts their tags in the
+/// `tags.data` next to them.
+pub fn append_intervals(files: &BTreeMap<PathBuf, Vec<String>>) -> std::io::Result<()> {
+    let mut tags: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    for (path, lines) in files {
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        std::fs::create_dir_all(dir)?;
+        let mut lines = lines.clone();
+        lines.sort_by(|a, b| start_of(a).cmp(&start_of(b)));
+        let existing = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let last = existing.lines().filter_map(|l| start_of(l)).max();
+        if last.is_some_and(|last| lines.first().and_then(|l| start_of(l)) < Some(last)) {
+            // Timewarrior expects intervals sorted by start, so earlier ones
+            // are merged in rather than appended.
+            let mut merged: Vec<&str> =
+                existing.lines().chain(lines.iter().map(String::as_str)).collect();
+            merged.sort_by(|a, b| start_of(a).cmp(&start_of(b)));
+            let tmp = path.with_extension("data.pompom");
+            std::fs::write(&tmp, merged.iter().map(|l| format!("{l}\n")).collect::<String>())?;
+            std::fs::rename(&tmp, path)?;
+        } else {
+            let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+            // Otherwise the first line would run on from the last one.
+            if !existing.is_empty() && !existing.ends_with('\n') {
+                writeln!(file)?;
+            }
+            for line in &lines {
+                writeln!(file, "{line}")?;
+            }
+        }
+        tags.entry(dir.join("tags.data"))
+            .or_default()
+            .extend(lines.iter().flat_map(|l| tags_of(l)));
+    }
+    for (path, new) in tags {
+        count_tags(&path, &new)?;
+    }
+    Ok(())
+}
+
+/// The start of an interval line, which sorts like the time it is.
+fn start_of(line: &str) -> Option<&str> {
+    let mut words = line.split_whitespace();
+    (words.next() == Some("inc")).then(|| words.next()).flatten()
+}
+
+/// The tags of an [`interval_line`], unquoted.
+fn tags_of(line: &str) -> Vec<String> {
+    let Some((_, rest)) = line.split_once(" # ") else {
+        return Vec::new();
+    };
+    let mut tags = Vec::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' => {}
+            // The annotation follows the tags.
+            '#' => break,
+            '"' => {
+                let mut tag = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => tag.extend(chars.next()),
+                        '"' => break,
+                        c => tag.push(c),
+                    }
+                }
+                tags.push(tag);
+            }
+            c => {
+                let mut tag = String::from(c);
+                tag.extend(chars.by_ref().take_while(|c| *c != ' '));
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+/// Adds one use of each of `tags` to Timewarrior's `tags.data`, a JSON
+/// object of `{"count": n}` per tag.
+fn count_tags(path: &Path, tags: &[String]) -> std::io::Result<()> {
+    let mut counts = match std::fs::read_to_string(path) {
+        Ok(contents) => {
+            serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&contents)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_json::Map::new(),
+        Err(e) => return Err(e),
+    };
+    for tag in tags {
+        let entry = counts.entry(tag.clone()).or_insert_with(|| serde_json::json!({ "count": 0 }));
+        let Some(entry) = entry.as_object_mut() else {
+            log::warn!("Leaving tag `{tag}` in {} alone, it isn't an object", path.display());
+            continue;
+        };
+        let count = entry.get("count").and_then(serde_json::Value::as_u64).unwrap_or_default();
+        entry.insert(String::from("count"), serde_json::Value::from(count + 1));
+    }
+    let mut json = serde_json::to_string_pretty(&counts)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    json.push('\n');
+    std::fs::write(path, json)
+}
@@ -0,0 +1,201 @@
+//! Webhooks posted on pomodoro state transitions, e.g. to a chat bot or a
+//! Home Assistant instance.
+//!
+//! ```toml
+//! [[webhooks]]
+//! url = "http://homeassistant.local:8123/api/webhook/pompom"
+//! events = ["work_start", "work_end"]
+//!
+//! [[webhooks]]
+//! url = "http://localhost:8080/notify"
+//! headers = { Authorization = "Bearer {{env.BOT_TOKEN}}" }
+//! payload = '{"text": "{{event}}: {{task}}, {{remaining}}s left"}'
+//! timeout = "2s"
+//! retries = 5
+//! ```
+//!
+//! Headers and payload are templates. `{{event}}`, `{{state}}`, `{{task}}`,
+//! `{{cycle}}`, `{{cycles}}`, `{{remaining}}` (seconds) and `{{at}}` (RFC
+//! 3339) are replaced by the transition, `{{env.NAME}}` by an environment
+//! variable. Values in the payload are escaped as JSON strings. Without a
+//! payload template the body is a JSON object with all of them.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use miette::{IntoDiagnostic, WrapErr};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Client, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::SetEvents,
+    hooks::{HookContext, HookEvent},
+    HumanDuration,
+};
+
+/// A URL notified of transitions, from a `[[webhooks]]` table of the config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Events to send. Empty sends all of them.
+    #[serde(default)]
+    pub events: Vec<HookEvent>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Template of the request body, a JSON object of the transition if unset.
+    #[serde(default)]
+    pub payload: Option<String>,
+    /// Give up on a single attempt after this long.
+    #[serde(default = "default_timeout")]
+    pub timeout: HumanDuration,
+    /// Attempts after the first when the request fails or the server answers
+    /// with a 5xx or 429 status.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each one after.
+    #[serde(default = "default_backoff")]
+    pub backoff: HumanDuration,
+}
+
+fn default_timeout() -> HumanDuration {
+    HumanDuration::from_secs(5)
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+fn default_backoff() -> HumanDuration {
+    HumanDuration::from_secs(1)
+}
+
+impl Webhook {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            events: Vec::new(),
+            headers: BTreeMap::new(),
+            payload: None,
+            timeout: default_timeout(),
+            retries: default_retries(),
+            backoff: default_backoff(),
+        }
+    }
+
+    pub fn wants(&self, event: HookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+
+    /// Sends `event`, retrying with exponential backoff. Returns the number of
+    /// attempts it took.
+    pub async fn send(
+        &self,
+        event: HookEvent,
+        context: &HookContext<'_>,
+        at: DateTime<Utc>,
+    ) -> miette::Result<u32> {
+        let vars = context.variables(event.as_str(), at);
+        let body = match &self.payload {
+            Some(template) => render(template, &vars, json_escape),
+            None => context.to_json(event.as_str(), at),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        for (name, template) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Invalid webhook header name `{name}`"))?;
+            let value = HeaderValue::from_str(&render(template, &vars, str::to_string))
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Invalid value for webhook header `{name}`"))?;
+            headers.insert(name, value);
+        }
+        let client = Client::builder()
+            .timeout(self.timeout.as_std())
+            .build()
+            .into_diagnostic()
+            .wrap_err("Failed to set up the webhook client")?;
+
+        let mut delay = self.backoff.as_std();
+        let mut attempt = 1;
+        loop {
+            let response =
+                client.post(&self.url).headers(headers.clone()).body(body.clone()).send().await;
+            let failure = match response {
+                Ok(response) if response.status().is_success() => return Ok(attempt),
+                Ok(response) if !retryable(response.status()) => {
+                    return Err(miette::miette!("{} answered {}", self.url, response.status()));
+                }
+                Ok(response) => miette::miette!("{} answered {}", self.url, response.status()),
+                Err(e) => miette::miette!("{}: {}", self.url, e),
+            };
+            if attempt > self.retries {
+                return Err(failure.wrap_err(format!("Gave up after {attempt} attempts")));
+            }
+            log::debug!("Webhook attempt {attempt} failed, retrying in {delay:?}: {failure}");
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+fn retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Sends `event` to each webhook that wants it. Failures are logged and
+/// never stop the timer.
+pub async fn notify_all(webhooks: &[Webhook], event: HookEvent, context: &HookContext<'_>) {
+    let at = Utc::now();
+    for webhook in webhooks.iter().filter(|w| w.wants(event)) {
+        if let Err(e) = webhook.send(event, context, at).await {
+            log::warn!("{event} webhook {e:?}");
+        }
+    }
+}
+
+/// Posts each event of the set to the webhooks that want it.
+pub async fn follow(webhooks: Vec<Webhook>, mut events: SetEvents) {
+    while let Some(event) = events.next().await {
+        if let Some((hook, context)) = HookEvent::of(&event) {
+            notify_all(&webhooks, hook, &context).await;
+        }
+    }
+}
+
+/// `s` as the inside of a JSON string literal.
+fn json_escape(s: &str) -> String {
+    let quoted = serde_json::Value::from(s).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Replaces the `{{name}}` placeholders of `template`, passing each value
+/// through `escape`. Unknown placeholders are left as they are.
+fn render(template: &str, vars: &[(&str, String)], escape: fn(&str) -> String) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 2..];
+        let Some(close) = after.find("}}") else {
+            rest = &rest[open..];
+            break;
+        };
+        let name = after[..close].trim();
+        let value = match name.strip_prefix("env.") {
+            Some(var) => std::env::var(var).ok(),
+            None => vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.clone()),
+        };
+        match value {
+            Some(value) => out.push_str(&escape(&value)),
+            None => out.push_str(&rest[open..open + close + 4]),
+        }
+        rest = &after[close + 2..];
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,243 @@
+//! `pompom interactive`: sets up a pomodoro set step by step, and can save
+//! it as a profile.
+//!
+//! `Ctrl+c` at any step, or `Esc` on a choice, cancels without starting
+//! anything.
+
+use std::io;
+
+use dialoguer::{
+    console::{Style, Term},
+    theme::ColorfulTheme,
+    Confirm, FuzzySelect, Input, Select,
+};
+
+use crate::{
+    config::{Config, Profile},
+    db::Database,
+    error::PomodoroError,
+    reminders::{ReminderStyle, Reminders, VOICES},
+    source::TaskRef,
+    HumanDuration,
+};
+
+/// How the set was set up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setup {
+    pub profile: Profile,
+    /// Name of the profile picked or saved, `None` for custom durations.
+    pub profile_name: Option<String>,
+    pub task: Option<(TaskRef, String)>,
+    pub reminders: Reminders,
+}
+
+/// Why the wizard stopped before the end.
+enum Stop {
+    Cancelled,
+    Failed(io::Error),
+}
+
+impl From<io::Error> for Stop {
+    fn from(e: io::Error) -> Self {
+        // Returned for `Ctrl+c`, which prompts read as a key.
+        if e.kind() == io::ErrorKind::Interrupted {
+            Stop::Cancelled
+        } else {
+            Stop::Failed(e)
+        }
+    }
+}
+
+/// Runs the wizard, starting from `profile`, the one `--profile` or the
+/// config picked. `None` if it was cancelled.
+pub fn run(
+    config: &Config,
+    profile: &Profile,
+    profile_name: &str,
+) -> miette::Result<Option<Setup>> {
+    let theme =
+        ColorfulTheme { values_style: Style::new().yellow().dim(), ..ColorfulTheme::default() };
+    // Prompts raise `SIGINT` on `Ctrl+c`, which would kill pompom with the
+    // cursor hidden. Handled, it comes back as `Interrupted` instead.
+    #[cfg(unix)]
+    let _interrupts = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt());
+    println!("Welcome to the setup wizard");
+    match setup(&theme, config, profile, profile_name) {
+        Ok(setup) => Ok(Some(setup)),
+        Err(Stop::Cancelled) => {
+            // Left hidden by a select that was interrupted.
+            let _shown = Term::stdout().show_cursor();
+            println!("Cancelled, nothing was started");
+            Ok(None)
+        }
+        Err(Stop::Failed(e)) => Err(PomodoroError::Prompt(e).into()),
+    }
+}
+
+fn setup(
+    theme: &ColorfulTheme,
+    config: &Config,
+    profile: &Profile,
+    profile_name: &str,
+) -> Result<Setup, Stop> {
+    let (mut profile, mut profile_name) = pick_profile(theme, config, profile, profile_name)?;
+    profile.cycles = Input::with_theme(theme)
+        .with_prompt("Work cycles before the long break")
+        .default(profile.cycles)
+        .validate_with(|cycles: &u16| if *cycles == 0 { Err("At least one") } else { Ok(()) })
+        .interact_text()?;
+    let task = pick_task(theme, config)?;
+    let reminders = pick_reminders(theme, &config.reminders)?;
+
+    println!();
+    println!("{}", summary(&profile));
+    if let Some((_, title)) = &task {
+        println!("Task: {title}");
+    }
+    match &reminders.voice {
+        Some(voice) => println!("Reminders: {} ({voice})", reminders.style),
+        None => println!("Reminders: {}", reminders.style),
+    }
+    println!();
+
+    let save = Confirm::with_theme(theme)
+        .with_prompt("Save as a profile?")
+        .default(false)
+        .interact_opt()?
+        .ok_or(Stop::Cancelled)?;
+    if save {
+        let name: String =
+            Input::with_theme(theme)
+                .with_prompt("Profile name")
+                .with_initial_text(profile_name.clone().unwrap_or_default())
+                .validate_with(|name: &String| {
+                    if name.trim().is_empty() {
+                        Err("A name is needed")
+                    } else {
+                        Ok(())
+                    }
+                })
+                .interact_text()?;
+        let name = name.trim().to_string();
+        save_profile(&name, &profile);
+        profile_name = Some(name);
+    }
+
+    let start = Confirm::with_theme(theme)
+        .with_prompt("Start the set?")
+        .default(true)
+        .interact_opt()?
+        .ok_or(Stop::Cancelled)?;
+    if !start {
+        return Err(Stop::Cancelled);
+    }
+    Ok(Setup { profile, profile_name, task, reminders })
+}
+
+/// A profile of the config, or custom durations starting from `profile`.
+fn pick_profile(
+    theme: &ColorfulTheme,
+    config: &Config,
+    profile: &Profile,
+    profile_name: &str,
+) -> Result<(Profile, Option<String>), Stop> {
+    let mut items: Vec<String> =
+        config.profiles.iter().map(|(name, p)| format!("{name}: {}", summary(p))).collect();
+    items.push("Custom durations".to_string());
+    let custom = items.len() - 1;
+    let picked = Select::with_theme(theme)
+        .with_prompt("Profile")
+        .items(&items)
+        .default(config.profiles.keys().position(|name| name == profile_name).unwrap_or(custom))
+        .interact_opt()?
+        .ok_or(Stop::Cancelled)?;
+    if let Some((name, picked)) = config.profiles.iter().nth(picked) {
+        return Ok((picked.clone(), Some(name.clone())));
+    }
+
+    let duration = |prompt: &str, default: HumanDuration| {
+        Input::with_theme(theme).with_prompt(prompt).default(default).interact_text()
+    };
+    let profile = Profile {
+        work: duration("Work time", profile.work)?,
+        short_break: duration("Short break", profile.short_break)?,
+        long_break: duration("Long break", profile.long_break)?,
+        cycles: profile.cycles,
+    };
+    Ok((profile, None))
+}
+
+/// A queued task or an open item of a source, found by typing part of its
+/// title.
+fn pick_task(theme: &ColorfulTheme, config: &Config) -> Result<Option<(TaskRef, String)>, Stop> {
+    let mut tasks = Vec::new();
+    match Database::open_default().and_then(|db| db.pending_tasks()) {
+        Ok(pending) => tasks.extend(
+            pending.into_iter().filter_map(|task| Some((TaskRef::Local(task.id?), task.title))),
+        ),
+        Err(e) => log::warn!("Failed to read the task queue: {e}"),
+    }
+    for source in config.sources.all() {
+        match source.open_items() {
+            Ok(items) => tasks.extend(items.into_iter().map(|item| {
+                (TaskRef::Source { source: source.name().to_string(), id: item.id }, item.title)
+            })),
+            Err(e) => log::warn!("{e:?}"),
+        }
+    }
+    if tasks.is_empty() {
+        return Ok(None);
+    }
+
+    let mut items = vec!["No task".to_string()];
+    items.extend(tasks.iter().map(|(task, title)| format!("{task}  {title}")));
+    let picked = FuzzySelect::with_theme(theme)
+        .with_prompt("Task (type to search)")
+        .items(&items)
+        .default(0)
+        .interact_opt()?
+        .ok_or(Stop::Cancelled)?;
+    Ok(picked.checked_sub(1).and_then(|i| tasks.into_iter().nth(i)))
+}
+
+fn pick_reminders(theme: &ColorfulTheme, current: &Reminders) -> Result<Reminders, Stop> {
+    let styles = ReminderStyle::ALL;
+    let style = Select::with_theme(theme)
+        .with_prompt("Reminders every five minutes and when a phase ends")
+        .items(&styles)
+        .default(styles.iter().position(|style| *style == current.style).unwrap_or(0))
+        .interact_opt()?
+        .ok_or(Stop::Cancelled)?;
+    let style = styles[style];
+    if !matches!(style, ReminderStyle::Speech | ReminderStyle::Both) {
+        return Ok(Reminders { style, voice: None });
+    }
+
+    let mut voices = vec!["Default voice"];
+    voices.extend(VOICES);
+    let current = current.voice.as_deref();
+    let voice = Select::with_theme(theme)
+        .with_prompt("Voice")
+        .items(&voices)
+        .default(voices.iter().position(|voice| Some(*voice) == current).unwrap_or(0))
+        .interact_opt()?
+        .ok_or(Stop::Cancelled)?;
+    let voice = (voice > 0).then(|| voices[voice].to_string());
+    Ok(Reminders { style, voice })
+}
+
+fn summary(profile: &Profile) -> String {
+    format!(
+        "work {}, short break {}, long break {} after {} cycles",
+        profile.work, profile.short_break, profile.long_break, profile.cycles
+    )
+}
+
+/// Adds `profile` to the config file as `[profiles.<name>]`. Failing to
+/// only costs the profile, the set still starts.
+fn save_profile(name: &str, profile: &Profile) {
+    match Config::save_profile(name, profile) {
+        Ok(()) => println!("Saved as profile `{name}`, use it with `pompom --profile {name}`"),
+        Err(e) => eprintln!("Warning: {e:?}"),
+    }
+}
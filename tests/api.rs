@@ -0,0 +1,108 @@
+//! The HTTP API of the main timer, on a free port.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use pompom::{
+    api::{self, ApiConfig, Command, Remote},
+    timers::Timers,
+};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use tokio::sync::mpsc;
+
+struct Api {
+    addr: SocketAddr,
+    remote: Arc<Remote>,
+    commands: mpsc::UnboundedReceiver<Command>,
+    client: Client,
+}
+
+impl Api {
+    fn serve() -> Self {
+        let (remote, commands) = Remote::new();
+        let config = ApiConfig { port: 0, token: "secret".into() };
+        let addr = api::serve(&config, Timers::new(Arc::clone(&remote))).unwrap();
+        Self { addr, remote, commands, client: Client::new() }
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.client.get(format!("http://{}{path}", self.addr)).bearer_auth("secret")
+    }
+
+    fn post(&self, path: &str) -> RequestBuilder {
+        self.client.post(format!("http://{}{path}", self.addr)).bearer_auth("secret")
+    }
+}
+
+#[tokio::test]
+async fn refuses_requests_without_the_token() {
+    let api = Api::serve();
+    let url = format!("http://{}/status", api.addr);
+
+    let missing = api.client.get(&url).send().await.unwrap();
+    assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(missing.headers()["www-authenticate"], "Bearer");
+    let wrong = api.client.get(&url).bearer_auth("secreT").send().await.unwrap();
+    assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+    let longer = api.client.get(&url).bearer_auth("secret2").send().await.unwrap();
+    assert_eq!(longer.status(), StatusCode::UNAUTHORIZED);
+
+    let right = api.get("/status").send().await.unwrap();
+    assert_eq!(right.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn answers_commands_that_dont_fit_the_state_with_conflict() {
+    let mut api = Api::serve();
+
+    let resume = api.post("/resume").send().await.unwrap();
+    assert_eq!(resume.status(), StatusCode::CONFLICT);
+    api.remote.update(|status| status.state = "work".into());
+    let resume = api.post("/resume").send().await.unwrap();
+    assert_eq!(resume.status(), StatusCode::CONFLICT);
+
+    let pause = api.post("/pause").send().await.unwrap();
+    assert_eq!(pause.status(), StatusCode::ACCEPTED);
+    assert_eq!(api.commands.try_recv(), Ok(Command::Pause));
+    let get = api.get("/pause").send().await.unwrap();
+    assert_eq!(get.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn starts_one_set_however_often_start_is_posted() {
+    let mut api = Api::serve();
+
+    let first = api.post("/start").send().await.unwrap();
+    assert_eq!(first.status(), StatusCode::ACCEPTED);
+    // Still idle, as the set hasn't started its first phase yet.
+    let second = api.post("/start").send().await.unwrap();
+    assert_eq!(second.status(), StatusCode::CONFLICT);
+    assert_eq!(api.commands.try_recv(), Ok(Command::Start));
+    assert!(api.commands.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn rejects_history_ranges_that_arent_dates() {
+    let api = Api::serve();
+
+    let from = api.get("/history?from=yesterday").send().await.unwrap();
+    assert_eq!(from.status(), StatusCode::BAD_REQUEST);
+    let unknown = api.get("/history?since=2026-10-01").send().await.unwrap();
+    assert_eq!(unknown.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn streams_events_starting_with_the_status() {
+    let api = Api::serve();
+
+    let mut events = api.get("/events").send().await.unwrap();
+    assert_eq!(events.status(), StatusCode::OK);
+    assert_eq!(events.headers()["content-type"], "text/event-stream");
+    let first = events.chunk().await.unwrap().unwrap();
+    let first = std::str::from_utf8(&first).unwrap();
+    assert!(first.starts_with("event: status\ndata: {\"state\":\"idle\""), "{first}");
+    assert!(first.ends_with("\n\n"), "{first}");
+
+    api.remote.broadcast("work_start", "{}");
+    let next = events.chunk().await.unwrap().unwrap();
+    assert_eq!(&next[..], b"event: work_start\ndata: {}\n\n");
+}
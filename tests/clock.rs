@@ -0,0 +1,117 @@
+//! A whole set on a manual clock: over an hour of pomodoros in milliseconds.
+
+use std::sync::Arc;
+
+use chrono::{Duration, TimeZone, Utc};
+use pompom::{
+    clock::ManualClock,
+    config::Profile,
+    db::Task,
+    events::{Event, PhaseKind},
+    hooks::HookEvent,
+    session::{Outcome, Timer},
+    HumanDuration,
+};
+
+#[tokio::test]
+async fn runs_a_four_cycle_set_with_pauses_and_reminders() {
+    let start = Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+    let clock = ManualClock::new(start);
+    let profile = Profile {
+        work: HumanDuration::from_mins(10),
+        short_break: HumanDuration::from_mins(2),
+        long_break: HumanDuration::from_mins(5),
+        cycles: 4,
+    };
+    let timer = Timer::new(profile).with_clock(Arc::new(clock.clone()));
+    let mut events = timer.subscribe();
+    let session = timer.start();
+
+    let second = HumanDuration::from_secs(1);
+    let pause = HumanDuration::from_mins(3);
+    let (mut sequence, mut ticks) = (Vec::new(), 0);
+    let ended_at = loop {
+        let event = events.recv().await.unwrap();
+        let name = match &event {
+            Event::ReminderDue { .. } => Some("reminder"),
+            Event::Resumed { .. } => Some("resumed"),
+            event => HookEvent::of(event).map(|(hook, _)| hook.as_str()),
+        };
+        sequence.extend(name);
+        match event {
+            // Skip the third short break as soon as it starts.
+            Event::PhaseStarted { phase }
+                if phase.kind == PhaseKind::ShortBreak && phase.cycle == 3 =>
+            {
+                session.skip().unwrap();
+            }
+            Event::PhaseStarted { .. } | Event::Resumed { .. } => clock.advance(second),
+            Event::Tick { remaining, .. } => {
+                ticks += 1;
+                // Pause the second work phase a third of the way in.
+                if ticks == 600 + 120 + 200 {
+                    session.pause().unwrap();
+                } else if remaining.as_secs() > 0 {
+                    clock.advance(second);
+                }
+            }
+            Event::Paused { .. } => {
+                clock.advance(pause);
+                session.resume().unwrap();
+            }
+            Event::SetCompleted { at, .. } => break at,
+            _ => {}
+        }
+    };
+    assert_eq!(timer.status().state, "idle");
+    assert_eq!(session.wait().await, Outcome::Completed);
+
+    let cycle = ["work_start", "reminder", "work_end", "break_start", "break_end"];
+    let paused =
+        ["work_start", "paused", "resumed", "reminder", "work_end", "break_start", "break_end"];
+    let expected = [&cycle[..], &paused, &cycle, &cycle, &["set_end"]].concat();
+    assert_eq!(sequence, expected);
+    // Four 10m work phases, two 2m short breaks and a 5m long break.
+    assert_eq!(ticks, 4 * 600 + 2 * 120 + 300);
+    let expected_end = start + Duration::seconds(ticks) + pause.to_chrono();
+    assert_eq!(ended_at, expected_end);
+}
+
+#[tokio::test]
+async fn works_through_queued_tasks_by_their_estimates() {
+    let start = Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+    let clock = ManualClock::new(start);
+    let profile = Profile {
+        work: HumanDuration::from_mins(1),
+        short_break: HumanDuration::from_secs(10),
+        long_break: HumanDuration::from_secs(30),
+        cycles: 4,
+    };
+    let tasks = vec![Task::new("Review"), Task::new("Write report").with_estimate(2)];
+    let timer = Timer::new(profile).with_clock(Arc::new(clock.clone())).with_tasks(tasks);
+    let mut events = timer.subscribe();
+    let session = timer.start();
+
+    let second = HumanDuration::from_secs(1);
+    let mut worked = Vec::new();
+    loop {
+        match events.recv().await.unwrap() {
+            Event::PhaseStarted { phase } if phase.kind == PhaseKind::Work => {
+                worked.push(phase.task.clone());
+                // A skipped pomodoro leaves the task where it was.
+                if phase.cycle == 2 {
+                    session.skip().unwrap();
+                } else {
+                    clock.advance(second);
+                }
+            }
+            Event::PhaseStarted { .. } => clock.advance(second),
+            Event::Tick { remaining, .. } if remaining.as_secs() > 0 => clock.advance(second),
+            Event::SetCompleted { .. } => break,
+            _ => {}
+        }
+    }
+    assert_eq!(session.wait().await, Outcome::Completed);
+    let report = Some(String::from("Write report"));
+    assert_eq!(worked, [Some("Review".into()), report.clone(), report.clone(), report]);
+}
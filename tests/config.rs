@@ -0,0 +1,105 @@
+//! Saving the config file, or parts of it, and loading it back.
+
+use chrono::{NaiveTime, Weekday};
+use pompom::{
+    config::{Config, Profile},
+    plan::BlockedRange,
+    schedule::{Schedule, WorkingHours},
+    HumanDuration,
+};
+
+fn at(hour: u32, min: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+}
+
+fn config() -> Config {
+    let weekdays = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+    Config {
+        working_hours: Some(WorkingHours {
+            start: at(9, 0),
+            end: at(17, 30),
+            days: weekdays.clone(),
+            timezone: chrono_tz::Europe::Berlin,
+            strict: false,
+        }),
+        schedules: vec![Schedule {
+            at: at(9, 30),
+            days: weekdays.clone(),
+            timezone: chrono_tz::UTC,
+        }],
+        blocked: vec![BlockedRange {
+            start: at(12, 0),
+            end: at(13, 0),
+            label: Some("Lunch".into()),
+            days: weekdays,
+        }],
+        ..Config::default()
+    }
+}
+
+#[test]
+fn saves_and_loads_back_a_config_with_tables_and_arrays() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pompom").join("config.toml");
+    let config = config();
+    config.save_to(&path).unwrap();
+    assert_eq!(Config::load_from(&path).unwrap(), config);
+}
+
+#[test]
+fn saving_schedules_keeps_the_rest_of_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    let written = "\
+# Mornings only.
+[working_hours]
+start = \"09:00\"
+end = \"12:00\"
+timezone = \"UTC\"
+
+[[blocked]]
+start = \"10:00\"
+end = \"10:15\"
+label = \"Standup\" # every day
+";
+    std::fs::write(&path, written).unwrap();
+
+    let mut config = Config::load_from(&path).unwrap();
+    config.schedules = self::config().schedules;
+    config.save_schedules_to(&path).unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.starts_with(written), "{saved}");
+    assert_eq!(Config::load_from(&path).unwrap(), config);
+
+    // Removing the last one leaves no `schedules` behind.
+    config.schedules.clear();
+    config.save_schedules_to(&path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), written);
+    assert_eq!(Config::load_from(&path).unwrap(), config);
+}
+
+#[test]
+fn saving_a_profile_adds_just_its_table() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    let written = "\
+profile = \"deep\" # the usual
+
+[profiles.deep]
+work = \"50m\"
+";
+    std::fs::write(&path, written).unwrap();
+
+    let quick = Profile {
+        work: HumanDuration::from_mins(15),
+        short_break: HumanDuration::from_mins(3),
+        long_break: HumanDuration::from_mins(10),
+        cycles: 2,
+    };
+    Config::save_profile_to(&path, "quick", &quick).unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.starts_with(written), "{saved}");
+    let config = Config::load_from(&path).unwrap();
+    assert_eq!(config.profile("quick").unwrap(), quick);
+    assert_eq!(config.profile("deep").unwrap().work, HumanDuration::from_mins(50));
+}
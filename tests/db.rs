@@ -0,0 +1,91 @@
+//! Migrating a history database written by an older pompom.
+
+use std::collections::HashSet;
+
+use pompom::db::{Database, SessionOutcome, SCHEMA_VERSION};
+use rusqlite::Connection;
+
+/// The schema at version 2, before tasks were linked and uuids added.
+const VERSION_2: &str = "
+    CREATE TABLE sessions (
+        id          INTEGER PRIMARY KEY,
+        kind        TEXT NOT NULL,
+        label       TEXT,
+        started_at  TEXT NOT NULL,
+        ended_at    TEXT NOT NULL
+    );
+    CREATE TABLE tasks (
+        id          INTEGER PRIMARY KEY,
+        title       TEXT NOT NULL,
+        estimate    INTEGER NOT NULL DEFAULT 1,
+        done        INTEGER NOT NULL DEFAULT 0,
+        created_at  TEXT NOT NULL
+    );
+    PRAGMA user_version = 2;
+";
+
+#[test]
+fn migrates_a_version_2_database_giving_every_row_a_uuid() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pompom.db");
+    {
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(VERSION_2).unwrap();
+        for hour in 9..14 {
+            conn.execute(
+                "INSERT INTO sessions (kind, started_at, ended_at) VALUES ('work', ?1, ?2)",
+                (
+                    format!("2026-10-19T{hour:02}:00:00+00:00"),
+                    format!("2026-10-19T{hour:02}:25:00+00:00"),
+                ),
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO tasks (title, created_at) VALUES (?1, '2026-10-19T08:00:00+00:00')",
+                [format!("Task {hour}")],
+            )
+            .unwrap();
+        }
+    }
+
+    let db = Database::open(&path).unwrap();
+    assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+    let sessions = db.sessions().unwrap();
+    let tasks = db.tasks().unwrap();
+    assert_eq!((sessions.len(), tasks.len()), (5, 5));
+    let uuids: HashSet<&str> = sessions
+        .iter()
+        .map(|session| session.uuid.as_str())
+        .chain(tasks.iter().map(|task| task.uuid.as_str()))
+        .collect();
+    assert_eq!(uuids.len(), 10);
+    assert!(uuids.iter().all(|uuid| uuid.len() == 36));
+    assert!(sessions.iter().all(|session| session.outcome == SessionOutcome::Completed));
+
+    // Opening it again finds nothing left to migrate.
+    drop(db);
+    assert_eq!(Database::open(&path).unwrap().sessions().unwrap(), sessions);
+}
+
+#[test]
+fn a_failed_migration_step_is_rolled_back_and_retried() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pompom.db");
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(VERSION_2).unwrap();
+    conn.execute_batch(
+        "ALTER TABLE sessions ADD COLUMN task TEXT;
+        ALTER TABLE sessions ADD COLUMN note TEXT;
+        PRAGMA user_version = 3;",
+    )
+    .unwrap();
+    // Step 4 alters `sessions` before it gets to the missing `tasks`.
+    conn.execute_batch("ALTER TABLE tasks RENAME TO queue").unwrap();
+
+    assert!(Database::open(&path).is_err());
+    let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+    assert_eq!(version, 3);
+
+    conn.execute_batch("ALTER TABLE queue RENAME TO tasks").unwrap();
+    assert_eq!(Database::open(&path).unwrap().schema_version().unwrap(), SCHEMA_VERSION);
+}
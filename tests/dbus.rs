@@ -0,0 +1,118 @@
+//! `org.pompom.Timer` on a private `dbus-daemon`.
+#![cfg(all(unix, not(target_os = "macos")))]
+
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::Arc,
+};
+
+use futures_util::StreamExt;
+use pompom::{
+    api::{self, Remote, Status},
+    dbus,
+};
+use zbus::{CacheProperties, Connection, ConnectionBuilder, Proxy, ProxyBuilder};
+
+/// A session bus of its own, killed when dropped.
+struct Bus {
+    daemon: Child,
+    address: String,
+}
+
+impl Bus {
+    /// `None` if `dbus-daemon` isn't installed.
+    fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+        Some(Self { daemon, address: address.trim().to_string() })
+    }
+
+    async fn connect(&self) -> Connection {
+        ConnectionBuilder::address(self.address.as_str()).unwrap().build().await.unwrap()
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+async fn timer(connection: &Connection) -> Proxy<'static> {
+    ProxyBuilder::new_bare(connection)
+        .destination(dbus::NAME)
+        .unwrap()
+        .path(dbus::PATH)
+        .unwrap()
+        .interface(dbus::NAME)
+        .unwrap()
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn controls_the_timer_over_the_bus() {
+    let Some(bus) = Bus::start() else {
+        eprintln!("dbus-daemon not found, skipping");
+        return;
+    };
+    let (remote, mut commands) = Remote::new();
+    let builder = ConnectionBuilder::address(bus.address.as_str()).unwrap();
+    let _service = dbus::serve_on(builder, Arc::clone(&remote)).await.unwrap();
+    let client = bus.connect().await;
+    let timer = timer(&client).await;
+
+    assert_eq!(timer.get_property::<String>("State").await.unwrap(), "idle");
+    let refused = timer.call_method("Pause", &()).await.unwrap_err();
+    assert!(refused.to_string().contains("No set is running"));
+
+    remote.update(|status| {
+        *status = Status {
+            state: "work".into(),
+            paused: false,
+            task: Some("Write report".into()),
+            cycle: 2,
+            cycles: 4,
+            remaining: 1500,
+        };
+    });
+    assert_eq!(timer.get_property::<String>("State").await.unwrap(), "work");
+    assert_eq!(timer.get_property::<u16>("Cycle").await.unwrap(), 2);
+    assert_eq!(timer.get_property::<u64>("Remaining").await.unwrap(), 1500);
+    assert_eq!(timer.get_property::<String>("Task").await.unwrap(), "Write report");
+
+    timer.call_method("Pause", &()).await.unwrap();
+    assert_eq!(commands.try_recv().unwrap(), api::Command::Pause);
+    timer.call_method("Stop", &()).await.unwrap();
+    assert_eq!(commands.try_recv().unwrap(), api::Command::Stop);
+}
+
+#[tokio::test]
+async fn signals_transitions() {
+    let Some(bus) = Bus::start() else {
+        eprintln!("dbus-daemon not found, skipping");
+        return;
+    };
+    let (remote, _commands) = Remote::new();
+    let builder = ConnectionBuilder::address(bus.address.as_str()).unwrap();
+    let _service = dbus::serve_on(builder, Arc::clone(&remote)).await.unwrap();
+    let client = bus.connect().await;
+    let timer = timer(&client).await;
+    let mut signals = timer.receive_signal("StateChanged").await.unwrap();
+
+    remote.update(|status| status.state = "short_break".into());
+    remote.broadcast("break_start", "{}");
+    let signal = signals.next().await.unwrap();
+    let (event, state): (String, String) = signal.body().unwrap();
+    assert_eq!((event.as_str(), state.as_str()), ("break_start", "short_break"));
+}
@@ -0,0 +1,107 @@
+//! A set's events as the built-in subscribers see them.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use pompom::{
+    api::{self, Remote},
+    events::{Event, EventBus, Phase, PhaseKind},
+    hooks::HookEvent,
+    metrics::{self, Metrics},
+    HumanDuration,
+};
+
+fn phase(kind: PhaseKind, task: Option<&str>) -> Phase {
+    Phase {
+        kind,
+        task: task.map(str::to_string),
+        cycle: 1,
+        cycles: 1,
+        length: HumanDuration::from_secs(3),
+        started_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn subscribers_follow_a_set_to_its_end() {
+    let bus = EventBus::default();
+    let (remote, _commands) = Remote::new();
+    let counts = Arc::new(Metrics::default());
+    let mut transitions = remote.subscribe();
+    let mut everything = bus.subscribe();
+    let mut set = bus.subscribe_set();
+    let applied = Arc::clone(&remote);
+    let followers = [
+        tokio::spawn(async move {
+            while let Some(event) = set.next().await {
+                applied.apply(&event);
+            }
+        }),
+        tokio::spawn(metrics::follow(Arc::clone(&counts), "deep".into(), bus.subscribe_set())),
+    ];
+
+    let work = phase(PhaseKind::Work, Some("Write report"));
+    let remaining = HumanDuration::from_secs(2);
+    bus.publish(Event::PhaseStarted { phase: work.clone() });
+    bus.publish(Event::Tick { kind: PhaseKind::Work, remaining });
+    bus.publish(Event::Paused { phase: work.clone(), remaining });
+    bus.publish(Event::Resumed { phase: work.clone(), remaining });
+    let elapsed = HumanDuration::from_secs(3);
+    let (ended_at, pauses, skipped) = (Utc::now(), 1, false);
+    bus.publish(Event::PhaseEnded { phase: work, ended_at, elapsed, pauses, skipped });
+    let long_break = phase(PhaseKind::LongBreak, None);
+    bus.publish(Event::PhaseStarted { phase: long_break.clone() });
+    bus.publish(Event::PhaseEnded { phase: long_break, ended_at, elapsed, pauses: 0, skipped });
+    let published = bus.publish(Event::SetCompleted { cycles: 1, at: Utc::now() });
+    assert_eq!(published, 8);
+
+    // Set subscriptions end with the set, so the followers return.
+    for follower in followers {
+        follower.await.unwrap();
+    }
+    assert_eq!(remote.status(), api::Status::default());
+    let mut events = Vec::new();
+    while let Ok(transition) = transitions.try_recv() {
+        events.push(transition.event);
+    }
+    let expected =
+        ["work_start", "paused", "resumed", "work_end", "break_start", "break_end", "set_end"];
+    assert_eq!(events, expected);
+    let text = counts.render(&remote.status(), chrono_tz::UTC, Utc::now());
+    assert!(text.contains(r#"pompom_pomodoros_today{task="Write report",profile="deep"} 1"#));
+    assert!(text.contains(r#"pompom_interruptions_total{task="Write report",profile="deep"} 1"#));
+
+    // Plain subscriptions see everything, ticks included.
+    let mut seen = Vec::new();
+    while let Ok(event) = everything.try_recv() {
+        seen.push(event);
+    }
+    assert_eq!(seen.len(), 8);
+    assert!(matches!(seen[1], Event::Tick { remaining: r, .. } if r == remaining));
+}
+
+#[tokio::test]
+async fn an_interrupted_phase_ends_the_set() {
+    let bus = EventBus::default();
+    let mut set = bus.subscribe_set();
+    let short_break = phase(PhaseKind::ShortBreak, None);
+    bus.publish(Event::PhaseStarted { phase: short_break.clone() });
+    bus.publish(Event::Interrupted {
+        phase: short_break,
+        ended_at: Utc::now(),
+        elapsed: HumanDuration::from_secs(1),
+        remaining: HumanDuration::from_secs(2),
+        pauses: 0,
+        cancelled: false,
+    });
+    bus.publish(Event::SetCompleted { cycles: 1, at: Utc::now() });
+
+    let started = set.next().await.unwrap();
+    let (hook, context) = HookEvent::of(&started).unwrap();
+    assert_eq!((hook, context.state), (HookEvent::BreakStart, "short_break"));
+    let interrupted = set.next().await.unwrap();
+    let (hook, context) = HookEvent::of(&interrupted).unwrap();
+    assert_eq!((hook, context.remaining.as_secs()), (HookEvent::Interrupted, 2));
+    assert_eq!(set.next().await, None);
+    assert_eq!(set.seen(), 2);
+}
@@ -0,0 +1,100 @@
+//! Scrapes `/metrics` the way Prometheus would.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use pompom::{
+    api::{Remote, Status},
+    events::{Event, Phase, PhaseKind},
+    metrics::{self, Metrics, MetricsConfig},
+    HumanDuration,
+};
+
+#[tokio::test]
+async fn scrapes_state_and_counters() {
+    let (remote, _commands) = Remote::new();
+    remote.update(|status| {
+        *status = Status {
+            state: "work".into(),
+            paused: true,
+            task: Some("Write report".into()),
+            cycle: 2,
+            cycles: 4,
+            remaining: 600,
+        };
+    });
+    let counts = Arc::new(Metrics::default());
+    let now = Utc::now();
+    let pomodoro = HumanDuration::from_mins(25);
+    counts.work_phase(Some("Write report"), "deep", now, pomodoro, 0, true);
+    counts.work_phase(Some("Write report"), "deep", now, pomodoro, 2, true);
+    counts.work_phase(Some("Say \"hi\""), "deep", now, HumanDuration::from_mins(5), 1, false);
+    counts.abandoned_break("deep");
+
+    let config = MetricsConfig { port: 0 };
+    let addr = metrics::serve(&config, Arc::clone(&remote), Arc::clone(&counts)).unwrap();
+    let response = reqwest::get(format!("http://{addr}/metrics")).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let text = response.text().await.unwrap();
+
+    let samples: Vec<&str> = text.lines().filter(|line| !line.starts_with('#')).collect();
+    for expected in [
+        r#"pompom_state{state="idle"} 0"#,
+        r#"pompom_state{state="work"} 1"#,
+        "pompom_paused 1",
+        "pompom_remaining_seconds 600",
+        r#"pompom_pomodoros_today{task="Write report",profile="deep"} 2"#,
+        r#"pompom_focus_seconds_total{task="Write report",profile="deep"} 3000"#,
+        r#"pompom_interruptions_total{task="Write report",profile="deep"} 2"#,
+        r#"pompom_abandoned_phases_total{task="Say \"hi\"",profile="deep"} 1"#,
+        r#"pompom_abandoned_phases_total{task="",profile="deep"} 1"#,
+    ] {
+        assert!(samples.contains(&expected), "missing `{expected}` in\n{text}");
+    }
+    for name in [
+        "pompom_state",
+        "pompom_pomodoros_today",
+        "pompom_focus_seconds_total",
+        "pompom_abandoned_phases_total",
+    ] {
+        assert!(text.contains(&format!("# TYPE {name} ")), "no TYPE for {name}");
+    }
+
+    let missing = reqwest::get(format!("http://{addr}/nope")).await.unwrap();
+    assert_eq!(missing.status(), 404);
+}
+
+#[test]
+fn counts_skipped_phases_as_abandoned() {
+    let counts = Metrics::default();
+    let now = Utc::now();
+    let ended = |kind, skipped| Event::PhaseEnded {
+        phase: Phase {
+            kind,
+            task: Some("Write report".into()),
+            cycle: 1,
+            cycles: 2,
+            length: HumanDuration::from_mins(25),
+            started_at: now,
+        },
+        ended_at: now,
+        elapsed: HumanDuration::from_mins(10),
+        pauses: 1,
+        skipped,
+    };
+    counts.record("deep", &ended(PhaseKind::Work, false));
+    counts.record("deep", &ended(PhaseKind::Work, true));
+    counts.record("deep", &ended(PhaseKind::ShortBreak, true));
+
+    let text = counts.render(&Status::default(), chrono_tz::UTC, now);
+    for expected in [
+        r#"pompom_pomodoros_today{task="Write report",profile="deep"} 1"#,
+        r#"pompom_focus_seconds_total{task="Write report",profile="deep"} 1200"#,
+        r#"pompom_interruptions_total{task="Write report",profile="deep"} 2"#,
+        r#"pompom_abandoned_phases_total{task="Write report",profile="deep"} 1"#,
+        r#"pompom_abandoned_phases_total{task="",profile="deep"} 1"#,
+    ] {
+        assert!(text.lines().any(|line| line == expected), "missing `{expected}` in\n{text}");
+    }
+}
@@ -0,0 +1,71 @@
+//! Named timers, driven through the HTTP API.
+
+use std::time::Duration;
+
+use pompom::{
+    api::{self, ApiConfig, Remote},
+    timers::{Named, NewTimer, Timers},
+    HumanDuration,
+};
+use reqwest::{Client, StatusCode};
+
+#[tokio::test]
+async fn named_timers_start_list_and_stop_on_their_own() {
+    let (main, _commands) = Remote::new();
+    let timers = Timers::new(main);
+    let config = ApiConfig { port: 0, token: "secret".into() };
+    let addr = api::serve(&config, timers.clone()).unwrap();
+    let client = Client::new();
+    let url = |path: &str| format!("http://{addr}{path}");
+
+    let standup = NewTimer::countdown(HumanDuration::from_mins(10), Some("standup".into()));
+    let started = client
+        .post(url("/timers/standup"))
+        .bearer_auth("secret")
+        .json(&standup)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(started.status(), StatusCode::CREATED);
+    let started: Named = started.json().await.unwrap();
+    assert_eq!(started.status.state, "work");
+    assert_eq!(started.status.task.as_deref(), Some("standup"));
+
+    let again =
+        client.post(url("/timers/standup")).bearer_auth("secret").json(&standup).send().await;
+    assert_eq!(again.unwrap().status(), StatusCode::CONFLICT);
+    let bad_name =
+        client.post(url("/timers/stand%20up")).bearer_auth("secret").json(&standup).send().await;
+    assert_eq!(bad_name.unwrap().status(), StatusCode::BAD_REQUEST);
+
+    let listed: Vec<Named> = client
+        .get(url("/timers"))
+        .bearer_auth("secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let names: Vec<_> = listed.iter().map(|timer| timer.name.as_str()).collect();
+    assert_eq!(names, ["pomodoro", "standup"]);
+    assert_eq!(listed[0].status.state, "idle");
+    assert_eq!(listed[1].status.cycles, 1);
+
+    // Stopping one leaves the main timer alone.
+    let stopped = client.post(url("/timers/standup/stop")).bearer_auth("secret").send().await;
+    assert_eq!(stopped.unwrap().status(), StatusCode::ACCEPTED);
+    for _ in 0..50 {
+        if timers.remote("standup").is_none() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(timers.remote("standup").is_none());
+    assert_eq!(timers.list().len(), 1);
+
+    let gone = client.get(url("/timers/standup")).bearer_auth("secret").send().await;
+    assert_eq!(gone.unwrap().status(), StatusCode::NOT_FOUND);
+    let main = client.post(url("/timers/pomodoro/stop")).bearer_auth("secret").send().await;
+    assert_eq!(main.unwrap().status(), StatusCode::CONFLICT);
+}
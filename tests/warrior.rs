@@ -0,0 +1,84 @@
+//! Exporting work phases into a Timewarrior data directory.
+
+use chrono::{Duration, TimeZone, Utc};
+use pompom::{
+    db::{Session, SessionKind, SessionOutcome},
+    warrior::{append_intervals, pending_intervals},
+};
+
+fn work(day: u32, hour: u32, task: &str) -> Session {
+    let start = Utc.with_ymd_and_hms(2026, 10, day, hour, 0, 0).unwrap();
+    Session::new(SessionKind::Work, start, start + Duration::minutes(25))
+        .with_task(Some(task.to_string()))
+}
+
+#[test]
+fn adds_missing_intervals_in_order_with_their_tags() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = dir.path().join("2026-10.data");
+    // Written by Timewarrior, without a newline at the end.
+    std::fs::write(&data, "inc 20261019T090000Z - 20261019T092500Z # pompom review").unwrap();
+    std::fs::write(dir.path().join("tags.data"), r#"{"pompom":{"count":1},"review":{"count":1}}"#)
+        .unwrap();
+
+    let sessions = [
+        work(19, 9, "review"),
+        work(19, 10, "Write report"),
+        Session::new(
+            SessionKind::ShortBreak,
+            Utc.with_ymd_and_hms(2026, 10, 19, 10, 25, 0).unwrap(),
+            Utc.with_ymd_and_hms(2026, 10, 19, 10, 30, 0).unwrap(),
+        ),
+    ];
+    let files = pending_intervals(dir.path(), &sessions).unwrap();
+    assert_eq!(
+        files[&data],
+        [r#"inc 20261019T100000Z - 20261019T102500Z # pompom "Write report""#]
+    );
+    append_intervals(&files).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&data).unwrap(),
+        "inc 20261019T090000Z - 20261019T092500Z # pompom review\n\
+         inc 20261019T100000Z - 20261019T102500Z # pompom \"Write report\"\n"
+    );
+    assert!(pending_intervals(dir.path(), &sessions).unwrap().is_empty());
+
+    // An earlier phase goes before the later ones.
+    let earlier = [work(18, 8, "review")];
+    append_intervals(&pending_intervals(dir.path(), &earlier).unwrap()).unwrap();
+    let starts: Vec<String> = std::fs::read_to_string(&data)
+        .unwrap()
+        .lines()
+        .map(|line| line[4..20].to_string())
+        .collect();
+    assert_eq!(starts, ["20261018T080000Z", "20261019T090000Z", "20261019T100000Z"]);
+
+    let tags: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("tags.data")).unwrap())
+            .unwrap();
+    assert_eq!(tags["pompom"]["count"], 3);
+    assert_eq!(tags["review"]["count"], 2);
+    assert_eq!(tags["Write report"]["count"], 1);
+}
+
+#[test]
+fn leaves_out_unfinished_phases_and_tags_it_cant_count() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("tags.data"), r#"{"pompom":{"count":4},"review":[]}"#).unwrap();
+
+    let sessions = [
+        work(19, 9, "review"),
+        work(19, 10, "Write report").with_outcome(SessionOutcome::Skipped),
+        work(19, 11, "Write report").with_outcome(SessionOutcome::Interrupted),
+    ];
+    let files = pending_intervals(dir.path(), &sessions).unwrap();
+    let lines: Vec<&String> = files.values().flatten().collect();
+    assert_eq!(lines, [r#"inc 20261019T090000Z - 20261019T092500Z # pompom review"#]);
+    append_intervals(&files).unwrap();
+
+    let tags: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("tags.data")).unwrap())
+            .unwrap();
+    assert_eq!(tags["pompom"]["count"], 5);
+    assert_eq!(tags["review"], serde_json::json!([]));
+}
@@ -0,0 +1,152 @@
+//! Webhooks sent to a local stand-in for the receiving server.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use chrono::{TimeZone, Utc};
+use pompom::{
+    hooks::{HookContext, HookEvent},
+    webhook::Webhook,
+    HumanDuration,
+};
+
+/// A request as the stand-in received it.
+struct Received {
+    head: String,
+    body: String,
+}
+
+/// Answers one connection per status in `statuses`, sending what it received
+/// over the channel. A status of 0 accepts the connection and never answers.
+fn stand_in(statuses: Vec<u16>) -> (String, mpsc::Receiver<Received>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for status in statuses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            tx.send(Received { head, body: String::from_utf8(body).unwrap() }).unwrap();
+            if status == 0 {
+                thread::sleep(Duration::from_secs(2));
+                continue;
+            }
+            write!(stream, "HTTP/1.1 {status} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .unwrap();
+        }
+    });
+    (url, rx)
+}
+
+fn context() -> HookContext<'static> {
+    HookContext {
+        state: "work",
+        task: Some("Write \"report\""),
+        cycle: 2,
+        cycles: 4,
+        remaining: HumanDuration::from_mins(25),
+    }
+}
+
+fn webhook(url: String) -> Webhook {
+    Webhook {
+        backoff: HumanDuration::from_secs(0),
+        timeout: HumanDuration::from_secs(1),
+        ..Webhook::new(url)
+    }
+}
+
+#[tokio::test]
+async fn posts_the_transition_as_json() {
+    let (url, rx) = stand_in(vec![200]);
+    let at = Utc.with_ymd_and_hms(2026, 10, 19, 7, 0, 0).unwrap();
+    let attempts = webhook(url).send(HookEvent::WorkStart, &context(), at).await.unwrap();
+    assert_eq!(attempts, 1);
+
+    let received = rx.recv().unwrap();
+    assert!(received.head.starts_with("POST /hook HTTP/1.1"));
+    assert!(received.head.to_ascii_lowercase().contains("content-type: application/json"));
+    let body: serde_json::Value = serde_json::from_str(&received.body).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "event": "work_start",
+            "state": "work",
+            "task": "Write \"report\"",
+            "cycle": 2,
+            "cycles": 4,
+            "remaining": 1500,
+            "at": "2026-10-19T07:00:00+00:00",
+        })
+    );
+}
+
+#[tokio::test]
+async fn renders_header_and_payload_templates() {
+    let (url, rx) = stand_in(vec![204]);
+    std::env::set_var("POMPOM_TEST_TOKEN", "s3cret");
+    let mut webhook = webhook(url);
+    webhook.headers.insert("Authorization".into(), "Bearer {{env.POMPOM_TEST_TOKEN}}".into());
+    webhook.payload = Some(r#"{"text": "{{event}} {{task}}, {{cycle}}/{{cycles}}"}"#.into());
+    webhook.send(HookEvent::WorkEnd, &context(), Utc::now()).await.unwrap();
+
+    let received = rx.recv().unwrap();
+    assert!(received.head.contains("authorization: Bearer s3cret"));
+    let body: serde_json::Value = serde_json::from_str(&received.body).unwrap();
+    assert_eq!(body["text"], "work_end Write \"report\", 2/4");
+}
+
+#[tokio::test]
+async fn retries_server_errors() {
+    let (url, rx) = stand_in(vec![503, 500, 200]);
+    let attempts = webhook(url).send(HookEvent::SetEnd, &context(), Utc::now()).await.unwrap();
+    assert_eq!(attempts, 3);
+    assert_eq!(rx.iter().count(), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_the_last_retry() {
+    let (url, rx) = stand_in(vec![503, 503]);
+    let mut webhook = webhook(url);
+    webhook.retries = 1;
+    let e = webhook.send(HookEvent::SetEnd, &context(), Utc::now()).await.unwrap_err();
+    assert!(format!("{e:?}").contains("Gave up after 2 attempts"));
+    assert_eq!(rx.iter().count(), 2);
+}
+
+#[tokio::test]
+async fn does_not_retry_client_errors() {
+    let (url, rx) = stand_in(vec![404, 200]);
+    let e = webhook(url).send(HookEvent::WorkStart, &context(), Utc::now()).await.unwrap_err();
+    assert!(e.to_string().contains("404"));
+    assert!(rx.recv().is_ok());
+    assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+}
+
+#[tokio::test]
+async fn times_out_a_silent_server() {
+    let (url, rx) = stand_in(vec![0, 200]);
+    let attempts = webhook(url).send(HookEvent::WorkStart, &context(), Utc::now()).await.unwrap();
+    assert_eq!(attempts, 2);
+    assert_eq!(rx.iter().count(), 2);
+}